@@ -0,0 +1,617 @@
+mod regression;
+
+use day_12::series::{Format, Series};
+use day_14::parse::Format as Format14;
+use grid::image::{self, Image, Palette};
+use grid::Dense;
+use regression::Status;
+use solution::Solution;
+
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+Usage:
+  aoc run DAY [--part N] [--input PATH]
+  aoc run --all
+  aoc bench DAY [--part N] [--input PATH] [--iterations N]
+  aoc test (DAY | --all) [--part N] [--record]
+  aoc draw DAY --output PATH [--input PATH] [--scale N] [--palette COLORS] [--animate]
+  aoc explain DAY [--input PATH] [--dot PATH]
+  aoc series DAY --output PATH [--input PATH] [--steps N] [--stride N]
+
+Inputs default to day_XX/src/input. `test` checks the answers recorded in
+day_XX/cases, and `--record` replaces them with the current answers. Day
+14 reads an `--input` PATH ending in .json or .toml as JSON or TOML
+reactions, with the `json` or `toml` feature.
+
+`draw` writes the picture of days 8, 11 and 13 in the format of the
+extension of PATH: .ppm, .pgm, .png or .gif. COLORS are comma separated
+RRGGBB colours for the cell values 0, 1, 2 and so on. With `--animate`
+a .gif PATH gets an animation, and other formats one numbered file per
+frame.
+
+`explain` prints how day 14 makes its answer, and `--dot` also writes it
+as a Graphviz graph.
+
+`series` runs the simulation of day 12 for N steps, 1000 by default, and
+writes the position, velocity and energies of every body at step 0 and
+every `--stride` steps after it. PATH is .csv, or .ndjson or .jsonl for
+one JSON object per line.";
+
+/// Shows each frame of an animation for this many hundredths of a second.
+const FRAME_DELAY: u16 = 4;
+
+/// The solutions, with day 14 reading an `input` file in the format of its
+/// extension.
+fn solutions(input: Option<&Path>) -> Vec<Box<dyn Solution>> {
+    vec![
+        Box::new(day_01::Day01),
+        Box::new(day_02::Day02),
+        Box::new(day_03::Day03),
+        Box::new(day_04::Day04),
+        Box::new(day_05::Day05),
+        Box::new(day_06::Day06),
+        Box::new(day_07::Day07),
+        Box::new(day_08::Day08),
+        Box::new(day_09::Day09),
+        Box::new(day_10::Day10),
+        Box::new(day_11::Day11),
+        Box::new(day_12::Day12),
+        Box::new(day_13::Day13),
+        Box::new(day_14::Day14::new(input.and_then(Format14::from_path))),
+    ]
+}
+
+type Frames = fn(&str) -> Result<Vec<Dense<u8>>, Box<dyn Error>>;
+
+/// A day whose answer is a picture: the frames that build it up, as
+/// palette indices, and the palette to draw them with.
+struct Drawing {
+    day: u32,
+    frames: Frames,
+    palette: fn() -> Palette,
+}
+
+fn drawings() -> Vec<Drawing> {
+    vec![
+        Drawing {
+            day: 8,
+            frames: day_08::frames,
+            palette: Palette::default,
+        },
+        Drawing {
+            day: 11,
+            frames: day_11::frames,
+            palette: Palette::default,
+        },
+        Drawing {
+            day: 13,
+            frames: day_13::frames,
+            palette: day_13::palette,
+        },
+    ]
+}
+
+type Explain = fn(&str, Option<&Path>) -> Result<String, Box<dyn Error>>;
+
+/// A day that can show how it gets its answer, as text and as a Graphviz
+/// graph, from its input and the `--input` file it came from.
+struct Explanation {
+    day: u32,
+    text: Explain,
+    dot: Explain,
+}
+
+fn explanations() -> Vec<Explanation> {
+    vec![Explanation {
+        day: 14,
+        text: |input, path| day_14::explain(input, path.and_then(Format14::from_path)),
+        dot: |input, path| day_14::explain_dot(input, path.and_then(Format14::from_path)),
+    }]
+}
+
+#[derive(Debug, Default)]
+struct Options {
+    /// `None` selects every day.
+    day: Option<u32>,
+    part: Option<u32>,
+    input: Option<PathBuf>,
+    iterations: Option<u32>,
+    record: bool,
+    output: Option<PathBuf>,
+    scale: Option<usize>,
+    palette: Option<Palette>,
+    animate: bool,
+    dot: Option<PathBuf>,
+    steps: Option<u64>,
+    stride: Option<u64>,
+}
+
+impl Options {
+    fn parse(args: &mut dyn Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut all = false;
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+            match arg.as_str() {
+                "--all" => all = true,
+                "--record" => options.record = true,
+                "--animate" => options.animate = true,
+                "--part" => {
+                    options.part = match value("--part")?.as_str() {
+                        "1" => Some(1),
+                        "2" => Some(2),
+                        part => return Err(format!("Invalid part: {}", part)),
+                    }
+                }
+                "--input" => options.input = Some(value("--input")?.into()),
+                "--iterations" => {
+                    let n = value("--iterations")?;
+                    options.iterations = Some(
+                        n.parse()
+                            .map_err(|_| format!("Invalid iterations: {}", n))?,
+                    );
+                }
+                "--output" => options.output = Some(value("--output")?.into()),
+                "--dot" => options.dot = Some(value("--dot")?.into()),
+                "--steps" => {
+                    let n = value("--steps")?;
+                    options.steps = Some(n.parse().map_err(|_| format!("Invalid steps: {}", n))?);
+                }
+                "--stride" => {
+                    let n = value("--stride")?;
+                    options.stride = match n.parse() {
+                        Ok(0) | Err(_) => return Err(format!("Invalid stride: {}", n)),
+                        Ok(n) => Some(n),
+                    };
+                }
+                "--scale" => {
+                    let n = value("--scale")?;
+                    options.scale = match n.parse() {
+                        Ok(0) | Err(_) => return Err(format!("Invalid scale: {}", n)),
+                        Ok(n) => Some(n),
+                    };
+                }
+                "--palette" => {
+                    options.palette =
+                        Some(value("--palette")?.parse().map_err(|e| format!("{}", e))?)
+                }
+                day if options.day.is_none() && !day.starts_with('-') => {
+                    options.day = Some(day.parse().map_err(|_| format!("Invalid day: {}", day))?);
+                }
+                _ => return Err(format!("Unexpected argument: {}", arg)),
+            }
+        }
+        match (all, options.day) {
+            (false, None) => Err("Specify a day or --all".to_string()),
+            (true, Some(_)) => Err("--all cannot be combined with a day".to_string()),
+            (true, None) if options.input.is_some() => {
+                Err("--input needs a single day".to_string())
+            }
+            _ => Ok(options),
+        }
+    }
+
+    /// Whether any of the options only `draw` uses were given.
+    fn draws(&self) -> bool {
+        self.scale.is_some() || self.palette.is_some() || self.animate
+    }
+
+    /// Whether any of the options only `series` uses were given.
+    fn records(&self) -> bool {
+        self.steps.is_some() || self.stride.is_some()
+    }
+
+    fn parts(&self) -> Vec<u32> {
+        match self.part {
+            Some(part) => vec![part],
+            None => vec![1, 2],
+        }
+    }
+}
+
+fn day_dir(day: u32) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join(format!("day_{:02}", day))
+}
+
+fn default_input(day: u32) -> PathBuf {
+    day_dir(day).join("src").join("input")
+}
+
+fn read_input(solution: &dyn Solution, options: &Options) -> Result<String, String> {
+    let path = match &options.input {
+        Some(path) => path.clone(),
+        None => default_input(solution.day()),
+    };
+    fs::read_to_string(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))
+}
+
+/// Formats an answer to follow a colon: on the same line, or on lines of
+/// its own if it spans several.
+fn show(answer: &str) -> String {
+    if answer.contains('\n') {
+        format!("\n{}", answer)
+    } else {
+        format!(" {}", answer)
+    }
+}
+
+fn solve(solution: &dyn Solution, part: u32, input: &str) -> solution::Result {
+    match part {
+        1 => solution.part1(input),
+        _ => solution.part2(input),
+    }
+}
+
+fn select<'a>(
+    solutions: &'a [Box<dyn Solution>],
+    options: &Options,
+) -> Result<Vec<&'a dyn Solution>, String> {
+    match options.day {
+        Some(day) => match solutions.iter().find(|s| s.day() == day) {
+            Some(solution) => Ok(vec![solution.as_ref()]),
+            None => Err(format!("No solution for day {}", day)),
+        },
+        None => Ok(solutions.iter().map(|s| s.as_ref()).collect()),
+    }
+}
+
+/// Solves the selected puzzles and prints the answers. Returns whether
+/// all of them succeeded.
+fn run(options: &Options) -> Result<bool, String> {
+    let solutions = solutions(options.input.as_deref());
+    let mut ok = true;
+    for solution in select(&solutions, options)? {
+        let input = match read_input(solution, options) {
+            Ok(input) => input,
+            Err(e) if options.day.is_none() => {
+                println!("Day {}: {}", solution.day(), e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        for part in options.parts() {
+            let start = Instant::now();
+            let result = solve(solution, part, &input);
+            let elapsed = start.elapsed();
+            let heading = format!("Day {}, part {} ({:.2?})", solution.day(), part, elapsed);
+            match result.map(|answer| answer.to_string()) {
+                Ok(answer) => println!("{}:{}", heading, show(&answer)),
+                Err(e) => {
+                    println!("{}: error: {}", heading, e);
+                    ok = false;
+                }
+            }
+        }
+    }
+    Ok(ok)
+}
+
+/// Times repeated runs of the selected puzzles.
+fn bench(options: &Options) -> Result<(), Box<dyn Error>> {
+    let iterations = options.iterations.unwrap_or(10).max(1);
+    let solutions = solutions(options.input.as_deref());
+    for solution in select(&solutions, options)? {
+        let input = read_input(solution, options)?;
+        for part in options.parts() {
+            // The first run warms up caches and checks that the part works.
+            solve(solution, part, &input)?;
+            let mut times = Vec::with_capacity(iterations as usize);
+            for _ in 0..iterations {
+                let start = Instant::now();
+                solve(solution, part, &input)?;
+                times.push(start.elapsed());
+            }
+            let total: Duration = times.iter().sum();
+            println!(
+                "Day {}, part {}: mean {:.2?}, min {:.2?}, max {:.2?} ({} runs)",
+                solution.day(),
+                part,
+                total / iterations,
+                times.iter().min().unwrap(),
+                times.iter().max().unwrap(),
+                iterations
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Checks the selected puzzles against their recorded answers. Returns
+/// whether none of them failed.
+fn test(options: &Options) -> Result<bool, Box<dyn Error>> {
+    let solutions = solutions(None);
+    let (mut passed, mut failed, mut unrecorded, mut recorded) = (0, 0, 0, 0);
+    for solution in select(&solutions, options)? {
+        let cases = regression::cases(&day_dir(solution.day()))?;
+        if cases.is_empty() {
+            println!("Day {}: no cases", solution.day());
+        }
+        for case in cases.iter() {
+            for part in options.parts() {
+                let (status, elapsed) = regression::check(solution, case, part, options.record)?;
+                let heading = format!(
+                    "Day {}, {}, part {} ({:.2?})",
+                    solution.day(),
+                    case.name,
+                    part,
+                    elapsed
+                );
+                match &status {
+                    Status::Pass => println!("{}: ok", heading),
+                    Status::Mismatch { expected, actual } => println!(
+                        "{}: MISMATCH\n  expected:{}\n  actual:{}",
+                        heading,
+                        show(expected),
+                        show(actual)
+                    ),
+                    Status::Unrecorded(actual) => {
+                        println!("{}: no recorded answer, got{}", heading, show(actual))
+                    }
+                    Status::Recorded { previous: None } => println!("{}: recorded", heading),
+                    Status::Recorded {
+                        previous: Some(previous),
+                    } => println!("{}: re-recorded, was{}", heading, show(previous)),
+                    Status::Error(e) => println!("{}: error: {}", heading, e),
+                }
+                match status {
+                    Status::Pass => passed += 1,
+                    Status::Unrecorded(_) => unrecorded += 1,
+                    Status::Recorded { .. } => recorded += 1,
+                    _ => failed += 1,
+                }
+            }
+        }
+    }
+    println!(
+        "{} passed, {} failed, {} unrecorded, {} recorded",
+        passed, failed, unrecorded, recorded
+    );
+    Ok(failed == 0)
+}
+
+/// Draws the picture of a day, or with `--animate` the frames that build
+/// it up, and prints the files written.
+fn draw(options: &Options) -> Result<(), Box<dyn Error>> {
+    let day = options.day.ok_or("draw needs a single day")?;
+    let output = options.output.as_ref().ok_or("draw needs --output")?;
+    let drawings = drawings();
+    let drawing = drawings
+        .iter()
+        .find(|d| d.day == day)
+        .ok_or_else(|| format!("Day {} has no picture to draw", day))?;
+    let path = match &options.input {
+        Some(path) => path.clone(),
+        None => default_input(day),
+    };
+    let input =
+        fs::read_to_string(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+
+    let palette = options.palette.clone().unwrap_or_else(drawing.palette);
+    let scale = options.scale.unwrap_or(1);
+    let mut frames = (drawing.frames)(&input)?;
+    if !options.animate {
+        frames = frames.split_off(frames.len().saturating_sub(1));
+    }
+    let images: Vec<_> = frames
+        .iter()
+        .map(|f| Image::new(f, &palette, scale))
+        .collect();
+    let saved = if options.animate {
+        image::save_animation(&images, output, FRAME_DELAY)
+    } else {
+        let image = images.first().ok_or("Nothing to draw")?;
+        image.save(output).map(|_| vec![output.clone()])
+    };
+    let paths = saved.map_err(|e| format!("Cannot write {}: {}", output.display(), e))?;
+    match paths.as_slice() {
+        [path] => println!("Wrote {}", path.display()),
+        paths => println!(
+            "Wrote {} frames, {} to {}",
+            paths.len(),
+            paths[0].display(),
+            paths[paths.len() - 1].display()
+        ),
+    }
+    Ok(())
+}
+
+/// Prints how a day gets its answer, and writes the Graphviz graph of it
+/// if asked to.
+fn explain(options: &Options) -> Result<(), Box<dyn Error>> {
+    let day = options.day.ok_or("explain needs a single day")?;
+    let explanations = explanations();
+    let explanation = explanations
+        .iter()
+        .find(|e| e.day == day)
+        .ok_or_else(|| format!("Day {} has no explanation", day))?;
+    let path = match &options.input {
+        Some(path) => path.clone(),
+        None => default_input(day),
+    };
+    let input =
+        fs::read_to_string(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let from = options.input.as_deref();
+    print!("{}", (explanation.text)(&input, from)?);
+    if let Some(dot) = &options.dot {
+        fs::write(dot, (explanation.dot)(&input, from)?)
+            .map_err(|e| format!("Cannot write {}: {}", dot.display(), e))?;
+        println!("Wrote {}", dot.display());
+    }
+    Ok(())
+}
+
+/// Writes the time series of a simulation.
+fn series(options: &Options) -> Result<(), Box<dyn Error>> {
+    let day = options.day.ok_or("series needs a single day")?;
+    if day != 12 {
+        return Err(format!("Day {} has no series", day).into());
+    }
+    let output = options.output.as_ref().ok_or("series needs --output")?;
+    let format = Format::from_path(output)
+        .ok_or_else(|| format!("Unknown series format: {}", output.display()))?;
+    let path = match &options.input {
+        Some(path) => path.clone(),
+        None => default_input(day),
+    };
+    let input =
+        fs::read_to_string(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+
+    let series = Series::new(
+        &input,
+        options.steps.unwrap_or(1000),
+        options.stride.unwrap_or(1),
+    )?;
+    File::create(output)
+        .and_then(|file| {
+            let mut out = BufWriter::new(file);
+            series.write(format, &mut out)?;
+            out.flush()
+        })
+        .map_err(|e| format!("Cannot write {}: {}", output.display(), e))?;
+    println!("Wrote {}", output.display());
+    Ok(())
+}
+
+fn usage(error: &str) -> ! {
+    eprintln!("{}\n\n{}", error, USAGE);
+    std::process::exit(2);
+}
+
+const COMMANDS: [&str; 6] = ["run", "test", "bench", "draw", "explain", "series"];
+
+/// Why `options` do not work with `command`, if they do not.
+fn misuse(command: &str, options: &Options) -> Option<&'static str> {
+    match command {
+        "run" | "bench" if options.record => Some("--record only works with test"),
+        "test" if options.input.is_some() => Some("test reads the recorded cases"),
+        "draw" | "explain" | "series" if options.record || options.part.is_some() => {
+            Some("draw, explain and series take neither --record nor --part")
+        }
+        _ if command != "draw" && options.draws() => {
+            Some("--scale, --palette and --animate only work with draw")
+        }
+        _ if command != "draw" && command != "series" && options.output.is_some() => {
+            Some("--output only works with draw and series")
+        }
+        _ if command != "series" && options.records() => {
+            Some("--steps and --stride only work with series")
+        }
+        _ if command != "explain" && options.dot.is_some() => Some("--dot only works with explain"),
+        _ => None,
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let command = args.next().unwrap_or_else(|| usage("Specify a command"));
+    if !COMMANDS.contains(&command.as_str()) {
+        usage(&format!("Unknown command: {}", command));
+    }
+    let options = Options::parse(&mut args).unwrap_or_else(|e| usage(&e));
+    if let Some(error) = misuse(&command, &options) {
+        usage(error);
+    }
+    let ok = match command.as_str() {
+        "run" => run(&options)?,
+        "test" => test(&options)?,
+        "bench" => {
+            bench(&options)?;
+            true
+        }
+        "draw" => {
+            draw(&options)?;
+            true
+        }
+        "explain" => {
+            explain(&options)?;
+            true
+        }
+        "series" => {
+            series(&options)?;
+            true
+        }
+        _ => unreachable!("{} is not in COMMANDS", command),
+    };
+    if !ok {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(&mut args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_options() {
+        let options = parse(&["7", "--part", "2", "--input", "in.txt"]).unwrap();
+        assert_eq!(options.day, Some(7));
+        assert_eq!(options.parts(), vec![2]);
+        assert_eq!(options.input, Some(PathBuf::from("in.txt")));
+
+        let options = parse(&["--all"]).unwrap();
+        assert_eq!(options.day, None);
+        assert_eq!(options.parts(), vec![1, 2]);
+
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["7", "--all"]).is_err());
+        assert!(parse(&["--all", "--input", "in.txt"]).is_err());
+        assert!(parse(&["7", "--part", "3"]).is_err());
+        assert!(parse(&["7", "8"]).is_err());
+
+        let options = parse(&["13", "--output", "a.gif", "--scale", "4", "--animate"]).unwrap();
+        assert_eq!(options.output, Some(PathBuf::from("a.gif")));
+        assert_eq!(options.scale, Some(4));
+        assert!(options.animate && options.draws());
+        let options = parse(&["8", "--palette", "000000,ffffff"]).unwrap();
+        assert_eq!(options.palette.unwrap().color(1), image::Rgb::WHITE);
+        assert!(!parse(&["8"]).unwrap().draws());
+        assert!(parse(&["8", "--scale", "0"]).is_err());
+        assert!(parse(&["8", "--palette", "white"]).is_err());
+
+        let options =
+            parse(&["12", "--output", "a.csv", "--steps", "50", "--stride", "5"]).unwrap();
+        assert_eq!((options.steps, options.stride), (Some(50), Some(5)));
+        assert!(options.records() && !options.draws());
+        assert!(parse(&["12", "--stride", "0"]).is_err());
+        assert!(parse(&["12", "--steps", "-1"]).is_err());
+    }
+
+    #[test]
+    fn test_misuse() {
+        let options = parse(&["12", "--output", "a.csv", "--steps", "5"]).unwrap();
+        assert_eq!(misuse("series", &options), None);
+        assert!(misuse("draw", &options).unwrap().contains("--steps"));
+        let options = parse(&["13", "--output", "a.gif", "--scale", "2"]).unwrap();
+        assert_eq!(misuse("draw", &options), None);
+        assert!(misuse("run", &options).unwrap().contains("--scale"));
+        let options = parse(&["7", "--record"]).unwrap();
+        assert_eq!(misuse("test", &options), None);
+        assert!(misuse("bench", &options).is_some());
+    }
+
+    #[test]
+    fn test_solutions() {
+        let days: Vec<_> = solutions(None).iter().map(|s| s.day()).collect();
+        assert_eq!(days, (1..=14).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_drawings() {
+        let days: Vec<_> = solutions(None).iter().map(|s| s.day()).collect();
+        assert!(drawings().iter().all(|d| days.contains(&d.day)));
+        assert!(explanations().iter().all(|e| days.contains(&e.day)));
+    }
+}
@@ -0,0 +1,188 @@
+//! Checks solutions against recorded answers.
+//!
+//! Every day has a set of cases. The puzzle input in `day_XX/src/input` is
+//! the case named `input`, and further inputs can be added to the
+//! `day_XX/cases` directory as `NAME.txt`. The expected answer to part N of
+//! a case is stored in `day_XX/cases/NAME.partN`, exactly as the solution
+//! displays it.
+
+use solution::Solution;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Case {
+    pub name: String,
+    pub input: PathBuf,
+    /// Directory holding the expected answers.
+    pub answers: PathBuf,
+}
+
+impl Case {
+    pub fn expected_path(&self, part: u32) -> PathBuf {
+        self.answers.join(format!("{}.part{}", self.name, part))
+    }
+}
+
+/// Lists the cases of the day in `day_dir`, sorted by name with the puzzle
+/// input first.
+pub fn cases(day_dir: &Path) -> io::Result<Vec<Case>> {
+    let answers = day_dir.join("cases");
+    let mut cases = Vec::new();
+    if answers.is_dir() {
+        for entry in fs::read_dir(&answers)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "txt") {
+                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                    cases.push(Case {
+                        name: name.to_string(),
+                        input: path.clone(),
+                        answers: answers.clone(),
+                    });
+                }
+            }
+        }
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let input = day_dir.join("src").join("input");
+    if input.is_file() {
+        cases.insert(
+            0,
+            Case {
+                name: "input".to_string(),
+                input,
+                answers,
+            },
+        );
+    }
+    Ok(cases)
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Status {
+    Pass,
+    Mismatch {
+        expected: String,
+        actual: String,
+    },
+    /// No answer has been recorded for the case.
+    Unrecorded(String),
+    /// The answer was written in record mode, replacing `previous`.
+    Recorded {
+        previous: Option<String>,
+    },
+    Error(String),
+}
+
+/// Solves one part of `case` and compares the answer with the recorded
+/// one, or records it if `record` is set.
+pub fn check(
+    solution: &dyn Solution,
+    case: &Case,
+    part: u32,
+    record: bool,
+) -> io::Result<(Status, Duration)> {
+    let input = fs::read_to_string(&case.input)?;
+    let start = Instant::now();
+    let result = match part {
+        1 => solution.part1(&input),
+        _ => solution.part2(&input),
+    };
+    let elapsed = start.elapsed();
+    let actual = match result {
+        Ok(answer) => answer.to_string(),
+        Err(e) => return Ok((Status::Error(e.to_string()), elapsed)),
+    };
+
+    let path = case.expected_path(part);
+    let expected = match fs::read_to_string(&path) {
+        Ok(expected) => Some(expected),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    // Editors tend to end a file edited by hand with a newline, so trailing
+    // whitespace does not count.
+    let status = match expected {
+        Some(expected) if expected.trim_end() == actual.trim_end() => Status::Pass,
+        previous if record => {
+            fs::create_dir_all(&case.answers)?;
+            fs::write(&path, &actual)?;
+            Status::Recorded { previous }
+        }
+        Some(expected) => Status::Mismatch { expected, actual },
+        None => Status::Unrecorded(actual),
+    };
+    Ok((status, elapsed))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Double;
+
+    impl Solution for Double {
+        fn day(&self) -> u32 {
+            1
+        }
+
+        fn part1(&self, input: &str) -> solution::Result {
+            let n: i64 = input.trim().parse()?;
+            solution::answer(2 * n)
+        }
+
+        fn part2(&self, _input: &str) -> solution::Result {
+            Err("Not solved".into())
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let dir = std::env::temp_dir().join(format!("aoc-regression-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("cases")).unwrap();
+        fs::write(dir.join("src").join("input"), "21\n").unwrap();
+        fs::write(dir.join("cases").join("small.txt"), "2\n").unwrap();
+        fs::write(dir.join("cases").join("small.part1"), "5").unwrap();
+
+        let cases = cases(&dir).unwrap();
+        let names: Vec<_> = cases.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["input", "small"]);
+
+        let status = |case, record| check(&Double, case, 1, record).unwrap().0;
+        assert_eq!(
+            status(&cases[0], false),
+            Status::Unrecorded("42".to_string())
+        );
+        assert_eq!(
+            status(&cases[1], false),
+            Status::Mismatch {
+                expected: "5".to_string(),
+                actual: "4".to_string()
+            }
+        );
+        assert_eq!(
+            status(&cases[1], true),
+            Status::Recorded {
+                previous: Some("5".to_string())
+            }
+        );
+        assert_eq!(status(&cases[1], false), Status::Pass);
+        assert_eq!(status(&cases[0], true), Status::Recorded { previous: None });
+        assert_eq!(status(&cases[0], false), Status::Pass);
+
+        fs::write(cases[1].expected_path(1), "4\n").unwrap();
+        assert_eq!(status(&cases[1], false), Status::Pass);
+
+        let (status, _) = check(&Double, &cases[0], 2, true).unwrap();
+        assert_eq!(status, Status::Error("Not solved".to_string()));
+        assert!(!cases[0].expected_path(2).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
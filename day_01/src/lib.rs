@@ -0,0 +1,40 @@
+use solution::{answer, Solution};
+
+fn module_fuel(m: i64) -> i64 {
+    m / 3 - 2
+}
+
+fn required_fuel(m: i64) -> i64 {
+    let mut total = 0;
+    let mut remaining = m;
+    loop {
+        remaining = module_fuel(remaining);
+        if remaining <= 0 {
+            break;
+        }
+        total += remaining;
+    }
+    total
+}
+
+fn parse(input: &str) -> Result<Vec<i64>, std::num::ParseIntError> {
+    input.lines()
+        .map(|s| s.trim().parse())
+        .collect()
+}
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    fn day(&self) -> u32 {
+        1
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        answer(parse(input)?.into_iter().map(module_fuel).sum::<i64>())
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        answer(parse(input)?.into_iter().map(required_fuel).sum::<i64>())
+    }
+}
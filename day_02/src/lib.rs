@@ -1,6 +1,4 @@
-use std::env;
-use std::fs::File;
-use std::io::Read;
+use solution::{answer, Solution};
 
 fn run_code(code: &mut [usize]) {
     let mut pos = 0;
@@ -15,34 +13,43 @@ fn run_code(code: &mut [usize]) {
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut code: Vec<usize> = {
-        let mut file = File::open(env::args().nth(1).unwrap())?;
-        let mut tmp = String::new();
-        file.read_to_string(&mut tmp)?;
-        tmp.trim()
-            .split(',')
-            .map(|s| s.parse())
-            .map(Result::unwrap)
-            .collect()
-    };
+fn parse(input: &str) -> Result<Vec<usize>, std::num::ParseIntError> {
+    input.trim()
+        .split(',')
+        .map(|s| s.parse())
+        .collect()
+}
+
+fn run_with(code: &[usize], noun: usize, verb: usize) -> usize {
+    let mut code = code.to_vec();
+    code[1] = noun;
+    code[2] = verb;
+    run_code(&mut code);
+    code[0]
+}
+
+pub struct Day02;
 
-    for noun in 0..=99 {
-        for verb in 0..=99 {
-            let mut code = code.clone();
-            code[1] = noun;
-            code[2] = verb;
-            run_code(&mut code);
-            if code[0] == 19690720 {
-                println!("noun = {}, verb = {}", noun, verb);
-                return Ok(());
+impl Solution for Day02 {
+    fn day(&self) -> u32 {
+        2
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        answer(run_with(&parse(input)?, 12, 2))
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        let code = parse(input)?;
+        for noun in 0..=99 {
+            for verb in 0..=99 {
+                if run_with(&code, noun, verb) == 19690720 {
+                    return answer(100 * noun + verb);
+                }
             }
         }
+        Err("No noun and verb produce 19690720".into())
     }
-
-    run_code(&mut code);
-    println!("Result: {}", code[0]);
-    Ok(())
 }
 
 #[cfg(test)]
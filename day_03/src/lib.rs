@@ -0,0 +1,256 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::num::ParseIntError;
+use std::convert::{From, TryFrom};
+use grid::Point;
+use solution::{answer, Solution};
+
+#[derive(Debug)]
+enum MoveError {
+    NumberError(ParseIntError),
+    DirectionError,
+    Empty
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NumberError(e) => write!(f, "Invalid move length: {}", e),
+            MoveError::DirectionError => write!(f, "Invalid move direction"),
+            MoveError::Empty => write!(f, "Empty move"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+impl From<ParseIntError> for MoveError {
+    fn from(e: ParseIntError) -> MoveError {
+        MoveError::NumberError(e)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Move {
+    Up(i64),
+    Down(i64),
+    Left(i64),
+    Right(i64)
+}
+
+impl Move {
+    /// One step of the move. Wires go up towards positive y, unlike the
+    /// rows of a `grid::Direction`.
+    fn step(&self) -> Point {
+        match *self {
+            Move::Up(_) => Point::new(0, 1),
+            Move::Down(_) => Point::new(0, -1),
+            Move::Left(_) => Point::new(-1, 0),
+            Move::Right(_) => Point::new(1, 0)
+        }
+    }
+
+    fn len(&self) -> i64 {
+        use Move::*;
+
+        match *self {
+            Up(n) => n,
+            Down(n) => n,
+            Left(n) => n,
+            Right(n) => n,
+        }
+    }
+
+    fn end(&self, start: Point) -> Point {
+        start + self.step() * self.len()
+    }
+
+    pub fn get_points(&self, start: &Point) -> HashSet<Point> {
+        let mut set = HashSet::new();
+        let step = self.step();
+        let mut start = *start + step;
+        for _ in 0..self.len() {
+            set.insert(start);
+            start += step;
+        }
+        set
+    }
+
+    fn steps_between(&self, start: &Point, end: &Point) -> Option<u64> {
+        let real_end = self.end(*start);
+        if end.x != start.x && end.y != start.y {
+            return None;  // point is not on the line
+        }
+        if end.x == start.x {
+            let b1 = start.y.min(real_end.y);
+            let b2 = start.y.max(real_end.y);
+            if b1 <= end.y && end.y <= b2 {
+                Some(start.manhattan(*end))
+            } else {
+                None
+            }
+        } else if end.y == start.y {
+            let b1 = start.x.min(real_end.x);
+            let b2 = start.x.max(real_end.x);
+            if b1 <= end.x && end.x <= b2 {
+                Some(start.manhattan(*end))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl std::str::FromStr for Move {
+    type Err = MoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Move::*;
+
+        let mut chars = s.chars();
+        let dir = chars.next().ok_or(MoveError::Empty)?;
+        let len = chars.as_str();
+        match dir {
+            'U' => Ok(Up(len.parse()?)),
+            'D' => Ok(Down(len.parse()?)),
+            'L' => Ok(Left(len.parse()?)),
+            'R' => Ok(Right(len.parse()?)),
+            _ => Err(MoveError::DirectionError)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Wire {
+    pub moves: Vec<(Point, Move)>
+}
+
+impl std::str::FromStr for Wire {
+    type Err = MoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut start = Point::ORIGIN;
+        let mut moves = vec![];
+        for part in s.trim().split(',') {
+            let dir: Move = part.parse()?;
+            moves.push((start, dir));
+            start = dir.end(start);
+        }
+        Ok(Wire { moves })
+    }
+}
+
+fn intersection(p1: &(Point, Move), p2: &(Point, Move)) -> Option<Point> {
+    let s1 = p1.1.get_points(&p1.0);
+    let s2 = p2.1.get_points(&p2.0);
+    s1.intersection(&s2).copied().min_by_key(|p| p.manhattan(Point::ORIGIN))
+}
+
+fn crossings(w1: &Wire, w2: &Wire) -> HashSet<Point> {
+    let mut all = HashSet::new();
+    for pos1 in w1.moves.iter() {
+        for pos2 in w2.moves.iter() {
+            if let Some(p) = intersection(pos1, pos2) {
+                all.insert(p);
+            }
+        }
+    }
+    all
+}
+
+fn wires_cross(w1: &Wire, w2: &Wire) -> Option<Point> {
+    crossings(w1, w2).iter()
+        .min_by(|p, q| {
+            let s1 = num_steps(w1, **p) + num_steps(w2, **p);
+            let s2 = num_steps(w1, **q) + num_steps(w2, **q);
+            s1.cmp(&s2)
+        }).copied()
+}
+
+fn num_steps(w: &Wire, intersection: Point) -> u64 {
+    let mut count = 0;
+    for (p, m) in w.moves.iter() {
+        if let Some(c) = m.steps_between(p, &intersection) {
+            count += c;
+            break;
+        } else {
+            count += m.len() as u64;
+        }
+    }
+    count
+}
+
+fn parse(input: &str) -> Result<(Wire, Wire), Box<dyn std::error::Error>> {
+    let wires: Vec<Wire> = input.lines()
+        .map(|line| line.parse())
+        .collect::<Result<_, _>>()?;
+    match <[Wire; 2]>::try_from(wires) {
+        Ok([w1, w2]) => Ok((w1, w2)),
+        Err(wires) => Err(format!("Expected 2 wires, found {}", wires.len()).into()),
+    }
+}
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    fn day(&self) -> u32 {
+        3
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        let (w1, w2) = parse(input)?;
+        let closest = crossings(&w1, &w2)
+            .into_iter()
+            .map(|p| p.manhattan(Point::ORIGIN))
+            .min()
+            .ok_or("Wires do not cross")?;
+        answer(closest)
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        let (w1, w2) = parse(input)?;
+        let p = wires_cross(&w1, &w2).ok_or("Wires do not cross")?;
+        answer(num_steps(&w1, p) + num_steps(&w2, p))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_num_steps() {
+        let w1 = "R8,U5,L5,D3".parse::<Wire>().unwrap();
+        assert_eq!(num_steps(&w1, Point::new(3, 3)), 20);
+    }
+
+    #[test]
+    fn test_1() {
+        let w1 = "R75,D30,R83,U83,L12,D49,R71,U7,L72".parse::<Wire>().unwrap();
+        let w2 = "U62,R66,U55,R34,D71,R55,D58,R83".parse::<Wire>().unwrap();
+        let x = wires_cross(&w1, &w2).unwrap();
+        assert_eq!(num_steps(&w1, x) + num_steps(&w2, x), 610);
+    }
+
+    #[test]
+    fn test_2() {
+        let w1 = "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51".parse::<Wire>().unwrap();
+        let w2 = "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7".parse::<Wire>().unwrap();
+        let x = wires_cross(&w1, &w2).unwrap();
+        assert_eq!(num_steps(&w1, x) + num_steps(&w2, x), 410);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!("".parse::<Move>(), Err(MoveError::Empty)));
+        assert!(matches!("U".parse::<Move>(), Err(MoveError::NumberError(_))));
+        assert!(matches!("é1".parse::<Move>(), Err(MoveError::DirectionError)));
+        assert!("R8,,U5".parse::<Wire>().is_err());
+        assert_eq!(
+            parse("R8,U\nU7").unwrap_err().to_string(),
+            "Invalid move length: cannot parse integer from empty string"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use solution::{answer, Solution};
+
+fn acceptable_1(num: u64) -> bool {
+    let s = num.to_string().into_bytes();
+    s.len() == 6
+        && s.windows(2).all(|w| w[0] <= w[1])
+        && s.windows(2).any(|w| w[0] == w[1])
+}
+
+fn acceptable(num: u64) -> bool {
+    let s = num.to_string();
+    if s.len() != 6 {
+        return false;
+    }
+    let mut adjacent_same = false;
+    let mut adjacent = HashMap::new();
+    for (d1, d2) in s.chars().zip(s.chars().skip(1)) {
+        let n1 = d1.to_digit(10).unwrap();
+        let n2 = d2.to_digit(10).unwrap();
+        if n1 == n2 {
+            let counter = adjacent.entry(n1).or_insert(1);
+            *counter += 1;
+            adjacent_same = true;
+        } else if n2 < n1 {
+            return false;
+        }
+    }
+    adjacent_same
+        && ((adjacent.len() > 1
+                && !adjacent.values().all(|&x| x == 3))
+            || adjacent.values().filter(|n| **n == 2).count() == 1)
+}
+
+/// Parses a range like `123456-654321`.
+fn parse(input: &str) -> Result<RangeInclusive<u64>, Box<dyn std::error::Error>> {
+    let mut bounds = input.trim().splitn(2, '-');
+    let start = bounds.next().unwrap_or_default().parse()?;
+    let end = bounds.next().ok_or("Expected a range like 123456-654321")?.parse()?;
+    Ok(start..=end)
+}
+
+pub struct Day04;
+
+impl Solution for Day04 {
+    fn day(&self) -> u32 {
+        4
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        answer(parse(input)?.filter(|n| acceptable_1(*n)).count())
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        answer(parse(input)?.filter(|n| acceptable(*n)).count())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        assert!(!acceptable(111111));
+        assert!(!acceptable(223450));
+        assert!(!acceptable(123789));
+    }
+
+    #[test]
+    fn test_part1() {
+        assert!(acceptable_1(111111));
+        assert!(!acceptable_1(223450));
+        assert!(!acceptable_1(123789));
+        assert_eq!(Day04.part1("111110-111112").unwrap().to_string(), "2");
+    }
+
+    #[test]
+    fn test_2() {
+        assert!(acceptable(112233));
+        assert!(!acceptable(123444));
+        assert!(acceptable(111122));
+        // The end of the range is itself a password.
+        assert_eq!(Day04.part2("112230-112233").unwrap().to_string(), "1");
+    }
+}
@@ -0,0 +1,30 @@
+use intcode::{load, Intcode, Word};
+use solution::{answer, Solution};
+
+/// Runs the diagnostic program for the system with `id` and returns the
+/// diagnostic code, the last value it outputs.
+fn diagnose(input: &str, id: Word) -> solution::Result {
+    let mut output = Vec::new();
+    let mut code = load::from_str(input)?;
+    code.as_mut_slice().run(&[id], &mut |r| output.push(r))?;
+    match output.last() {
+        Some(&code) => answer(code),
+        None => Err("Diagnostic program produced no output".into()),
+    }
+}
+
+pub struct Day05;
+
+impl Solution for Day05 {
+    fn day(&self) -> u32 {
+        5
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        diagnose(input, 1)
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        diagnose(input, 5)
+    }
+}
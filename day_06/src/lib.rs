@@ -1,16 +1,25 @@
 use std::cell::RefCell;
 use std::collections::{HashMap, VecDeque};
-use std::env;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fmt;
 use std::rc::Rc;
 use std::str::FromStr;
+use solution::{answer, Solution};
 
 #[derive(Debug)]
 enum Error {
     InvalidOrbit(String),
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidOrbit(s) => write!(f, "Invalid orbit: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 struct Orbit {
     pub center: String,
     pub object: String,
@@ -41,7 +50,7 @@ fn count_orbits_1(obj: &str, data: &HashMap<&str, &str>) -> u64 {
     }
 }
 
-fn count_orbits(orbits: &Vec<Orbit>) -> u64 {
+fn count_orbits(orbits: &[Orbit]) -> u64 {
     let mut map: HashMap<&str, &str> = HashMap::new();
     for o in orbits.iter() {
         map.insert(&o.object, &o.center);
@@ -65,10 +74,10 @@ impl Arena {
         Arena { nodes: Vec::new() }
     }
 
-    fn new_node(&mut self, name: &String) -> Rc<RefCell<Node>> {
+    fn new_node(&mut self, name: &str) -> Rc<RefCell<Node>> {
         let node = Rc::new(RefCell::new(Node {
             id: self.nodes.len(),
-            name: name.clone(),
+            name: name.to_string(),
             children: Vec::new(),
         }));
         self.nodes.push(node.clone());
@@ -80,7 +89,7 @@ impl Arena {
     }
 }
 
-fn make_orbit_graph(orbits: &Vec<Orbit>) -> (Arena, HashMap<String, Rc<RefCell<Node>>>) {
+fn make_orbit_graph(orbits: &[Orbit]) -> (Arena, HashMap<String, Rc<RefCell<Node>>>) {
     let mut arena = Arena::new();
     let mut graph = HashMap::new();
     for o in orbits.iter() {
@@ -110,10 +119,10 @@ fn make_orbit_graph(orbits: &Vec<Orbit>) -> (Arena, HashMap<String, Rc<RefCell<N
     (arena, graph)
 }
 
-fn min_transfers(orbits: &Vec<Orbit>, source: &str, dest: &str) -> u64 {
+fn min_transfers(orbits: &[Orbit], source: &str, dest: &str) -> u64 {
     let (arena, graph) = make_orbit_graph(orbits);
     let mut queue: VecDeque<(Rc<RefCell<Node>>, u64)> = VecDeque::new();
-    queue.push_front((graph.get(&source.to_string()).unwrap().clone(), 0));
+    queue.push_front((graph.get(source).unwrap().clone(), 0));
     let mut seen: HashMap<String, u64> = HashMap::new();
     seen.insert(source.to_string(), 0);
 
@@ -134,16 +143,22 @@ fn min_transfers(orbits: &Vec<Orbit>, source: &str, dest: &str) -> u64 {
     seen.get(&dest).unwrap() - 2
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let file = File::open(env::args().nth(1).unwrap())?;
-    let orbits: Vec<Orbit> = BufReader::new(file)
-        .lines()
-        .map(Result::unwrap)
-        .map(|s| s.parse())
-        .map(Result::unwrap)
-        .collect();
-    let count = count_orbits(&orbits);
-    println!("Total number of orbits: {}", count);
-    println!("Distance: {}", min_transfers(&orbits, "YOU", "SAN"));
-    Ok(())
+fn parse(input: &str) -> Result<Vec<Orbit>, Error> {
+    input.lines().map(|s| s.trim().parse()).collect()
+}
+
+pub struct Day06;
+
+impl Solution for Day06 {
+    fn day(&self) -> u32 {
+        6
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        answer(count_orbits(&parse(input)?))
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        answer(min_transfers(&parse(input)?, "YOU", "SAN"))
+    }
 }
@@ -0,0 +1,184 @@
+use std::error::Error;
+
+use intcode::network::{Network, Outcome};
+use intcode::{load, Program, RunState, Word};
+use solution::{answer, Solution};
+
+#[derive(Debug)]
+struct Amplifier {
+    program: Program,
+}
+
+impl Amplifier {
+    pub fn new(code: &[Word]) -> Amplifier {
+        Amplifier {
+            program: Program::new(code),
+        }
+    }
+
+    pub fn run(&mut self, phase_setting: Word, input_signal: Word) -> Result<Word, Box<dyn Error>> {
+        match self.run_partial(&[phase_setting, input_signal])? {
+            RunState::Output(out) => Ok(out),
+            state => Err(format!("Amplifier produced no output: {:?}", state).into()),
+        }
+    }
+
+    pub fn run_partial(&mut self, input: &[Word]) -> Result<RunState, intcode::Error> {
+        let mut input_iter = input.iter();
+        let mut get_input = move || input_iter.next().copied();
+        self.program.run_partial(&mut get_input)
+    }
+}
+
+fn permutations(state: &[Word; 5]) -> Vec<[Word; 5]> {
+    let mut state = *state;
+    let mut output = Vec::new();
+    let mut c = [0; 5];
+
+    output.push(state);
+
+    let mut i = 0;
+    while i < 5 {
+        if c[i] < i {
+            if i % 2 == 0 {
+                state.swap(0, i);
+            } else {
+                state.swap(c[i], i);
+            }
+            output.push(state);
+            c[i] += 1;
+            i = 0;
+        } else {
+            c[i] = 0;
+            i += 1;
+        }
+    }
+
+    output
+}
+
+fn max_signal(code: &[Word]) -> Result<Word, Box<dyn Error>> {
+    let signals = permutations(&[0, 1, 2, 3, 4])
+        .iter()
+        .map(|&p| {
+            let mut amplifiers: Vec<_> = (0..5).map(|_| Amplifier::new(code)).collect();
+            let mut input = 0;
+            for (amp, phase) in amplifiers.iter_mut().zip(p.iter()) {
+                input = amp.run(*phase, input)?;
+            }
+            Ok(input)
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+    Ok(signals.into_iter().max().unwrap())
+}
+
+fn max_feedback_signal(code: &[Word]) -> Result<Word, Box<dyn Error>> {
+    let signals = permutations(&[5, 6, 7, 8, 9])
+        .iter()
+        .map(|&p| run_feedback_loop(code, p))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(signals.into_iter().max().unwrap())
+}
+
+pub struct Day07;
+
+impl Solution for Day07 {
+    fn day(&self) -> u32 {
+        7
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        answer(max_signal(&load::from_str(input)?)?)
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        answer(max_feedback_signal(&load::from_str(input)?)?)
+    }
+}
+
+fn run_feedback_loop(code: &[Word], p: [Word; 5]) -> Result<Word, Box<dyn Error>> {
+    let amps = (0..5).map(|_| Program::new(code)).collect();
+    let mut network: Network = Network::ring(amps);
+    for (id, phase) in p.iter().enumerate() {
+        network.push_input(id, *phase);
+    }
+    network.push_input(0, 0);
+    match network.run()? {
+        Outcome::Halted => Ok(network
+            .last_output(4)
+            .ok_or("The last amplifier produced no output")?),
+        outcome => Err(format!("Feedback loop did not halt: {:?}", outcome).into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_1() {
+        let code = vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+        let mut amplifiers: Vec<_> = (0..5).map(|_| Amplifier::new(&code)).collect();
+        let phase_settings: [Word; 5] = [4, 3, 2, 1, 0];
+        let mut input = 0;
+        for (amp, phase) in amplifiers.iter_mut().zip(phase_settings.iter()) {
+            input = amp.run(*phase, input).unwrap();
+        }
+        assert_eq!(input, 43210);
+    }
+
+    #[test]
+    fn test_2() {
+        let code = vec![
+            3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23, 23, 1, 24, 23, 23, 4, 23,
+            99, 0, 0,
+        ];
+        let mut amplifiers: Vec<_> = (0..5).map(|_| Amplifier::new(&code)).collect();
+        let phase_settings: [Word; 5] = [0, 1, 2, 3, 4];
+        let mut input = 0;
+        for (amp, phase) in amplifiers.iter_mut().zip(phase_settings.iter()) {
+            input = amp.run(*phase, input).unwrap();
+        }
+        assert_eq!(input, 54321);
+    }
+
+    #[test]
+    fn test_3() {
+        let code = vec![
+            3, 31, 3, 32, 1002, 32, 10, 32, 1001, 31, -2, 31, 1007, 31, 0, 33, 1002, 33, 7, 33, 1,
+            33, 31, 31, 1, 32, 31, 31, 4, 31, 99, 0, 0, 0,
+        ];
+        let mut amplifiers: Vec<_> = (0..5).map(|_| Amplifier::new(&code)).collect();
+        let phase_settings: [Word; 5] = [1, 0, 4, 3, 2];
+        let mut input = 0;
+        for (amp, phase) in amplifiers.iter_mut().zip(phase_settings.iter()) {
+            input = amp.run(*phase, input).unwrap();
+        }
+        assert_eq!(input, 65210);
+    }
+
+    #[test]
+    fn test_feedback_1() {
+        let code = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        assert_eq!(
+            run_feedback_loop(&code, [9, 8, 7, 6, 5]).unwrap(),
+            139629729
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        // Halts without output, and fails on opcode 0.
+        assert!(max_signal(&[99]).is_err());
+        assert!(max_signal(&[3, 0, 3, 0, 0]).is_err());
+        assert!(run_feedback_loop(&[3, 0, 99], [9, 8, 7, 6, 5]).is_err());
+        // Every amplifier waits for input that never comes.
+        let code = [3, 5, 1105, 1, 0, 0];
+        assert!(run_feedback_loop(&code, [9, 8, 7, 6, 5]).is_err());
+    }
+}
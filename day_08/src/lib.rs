@@ -0,0 +1,79 @@
+use grid::{ocr, Dense};
+use solution::{answer, Solution};
+
+const WIDTH: usize = 25;
+const HEIGHT: usize = 6;
+
+fn parse(input: &str) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    input
+        .trim()
+        .chars()
+        .map(|c| {
+            c.to_digit(10)
+                .ok_or_else(|| format!("Invalid pixel: {:?}", c).into())
+        })
+        .collect()
+}
+
+fn layers(digits: &[u32]) -> Result<Vec<Dense<u32>>, Box<dyn std::error::Error>> {
+    if digits.is_empty() || !digits.len().is_multiple_of(WIDTH * HEIGHT) {
+        return Err(format!(
+            "{} pixels do not make {}x{} layers",
+            digits.len(),
+            WIDTH,
+            HEIGHT
+        )
+        .into());
+    }
+    Ok(digits
+        .chunks(WIDTH * HEIGHT)
+        .map(|layer| Dense::from_vec(WIDTH, layer.to_vec()))
+        .collect())
+}
+
+fn checksum(layers: &[Dense<u32>]) -> Option<usize> {
+    let count = |layer: &Dense<u32>, digit| layer.cells().iter().filter(|&&x| x == digit).count();
+    layers
+        .iter()
+        .min_by_key(|layer| count(layer, 0))
+        .map(|layer| count(layer, 1) * count(layer, 2))
+}
+
+/// Stacks the layers, the first on top. Pixels that are transparent (2)
+/// in every layer stay transparent.
+fn decode(layers: &[Dense<u32>]) -> Dense<u32> {
+    let pixels = layers[0]
+        .points()
+        .map(|p| layers.iter().map(|l| l[p]).find(|&x| x != 2).unwrap_or(2))
+        .collect();
+    Dense::from_vec(WIDTH, pixels)
+}
+
+/// The image as the layers are stacked from the bottom up, one frame per
+/// layer, in palette indices: 0 black, 1 white, 2 transparent.
+pub fn frames(input: &str) -> Result<Vec<Dense<u8>>, Box<dyn std::error::Error>> {
+    let layers = layers(&parse(input)?)?;
+    Ok((0..layers.len())
+        .rev()
+        .map(|top| {
+            let image = decode(&layers[top..]);
+            image.map(|&x| x as u8)
+        })
+        .collect())
+}
+
+pub struct Day08;
+
+impl Solution for Day08 {
+    fn day(&self) -> u32 {
+        8
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        answer(checksum(&layers(&parse(input)?)?).ok_or("Empty image")?)
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        answer(ocr::read(&decode(&layers(&parse(input)?)?).map(|&x| x == 1))?)
+    }
+}
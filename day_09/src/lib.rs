@@ -0,0 +1,29 @@
+use intcode::{load, Intcode, Word};
+use solution::{answer, Solution};
+
+/// Runs the BOOST program in `mode` and returns the keycode it outputs.
+fn boost(input: &str, mode: Word) -> solution::Result {
+    let mut output = Vec::new();
+    let mut code = load::from_str(input)?;
+    code.run(&[mode], &mut |r| output.push(r))?;
+    match output.first() {
+        Some(&keycode) => answer(keycode),
+        None => Err("BOOST program produced no output".into()),
+    }
+}
+
+pub struct Day09;
+
+impl Solution for Day09 {
+    fn day(&self) -> u32 {
+        9
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        boost(input, 1)
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        boost(input, 2)
+    }
+}
@@ -0,0 +1,141 @@
+use std::hash::{Hash, Hasher};
+use std::collections::HashSet;
+use grid::{Dense, Point};
+use solution::{answer, Solution};
+
+#[derive(PartialOrd, Clone, Copy, Debug)]
+struct Angle(f64);
+
+impl Hash for Angle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_string().hash(state);
+    }
+}
+
+impl PartialEq for Angle {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Angle {}
+
+
+fn unique_slopes(center: &Point, asteroids: &Vec<&&Point>) -> usize {
+    let mut slopes = HashSet::new();
+    for a in asteroids.iter() {
+        let x = (center.x - a.x) as f64;
+        let y = (center.y - a.y) as f64;
+        slopes.insert(y.atan2(x).to_string());
+    }
+    slopes.len()
+}
+
+fn calc_slopes(center: &Point, asteroids: &Vec<&Point>) -> usize {
+    let (x1, y1) = (center.x, center.y);
+    let quad1: Vec<_> = asteroids
+        .iter()
+        .filter(|&p| p.x <= x1 && p.y <= y1)
+        .collect();
+    let quad2: Vec<_> = asteroids
+        .iter()
+        .filter(|&p| p.x > x1 && p.y <= y1)
+        .collect();
+    let quad3: Vec<_> = asteroids
+        .iter()
+        .filter(|&p| p.x <= x1 && p.y > y1)
+        .collect();
+    let quad4: Vec<_> = asteroids.iter().filter(|&p| p.x > x1 && p.y > y1).collect();
+    [quad1, quad2, quad3, quad4]
+        .iter()
+        .map(|q| unique_slopes(center, q))
+        .sum()
+}
+
+fn angle(start: &Point, end: &Point) -> Angle {
+    use std::f64::consts::PI;
+    let result = ((end.x - start.x) as f64).atan2((start.y - end.y) as f64) * 180. / PI;
+    Angle(if result < 0. {
+        result + 360.
+    } else {
+        result
+    })
+}
+
+/// Parses the map. Whitespace around each row is ignored.
+fn parse(input: &str) -> Result<Vec<Point>, grid::ParseError> {
+    let rows: Vec<_> = input.lines().map(str::trim).collect();
+    let map = Dense::parse(&rows.join("\n"), |ch| match ch {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })?;
+    Ok(map.iter().filter(|(_, &asteroid)| asteroid).map(|(p, _)| p).collect())
+}
+
+/// Returns the asteroid that sees the most others, and how many it sees.
+fn best_station(coords: &[Point]) -> Option<(Point, usize)> {
+    let coords: Vec<_> = coords.iter().collect();
+    coords
+        .iter()
+        .map(|&&c| (c, calc_slopes(&c, &coords)))
+        .max_by_key(|(_, count)| *count)
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    fn day(&self) -> u32 {
+        10
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        let (_, count) = best_station(&parse(input)?).ok_or("No asteroids")?;
+        answer(count)
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        let coords = parse(input)?;
+        let (station, _) = best_station(&coords).ok_or("No asteroids")?;
+        let mut tmp: Vec<_> = coords
+            .iter()
+            .filter(|&p| p != &station)
+            .map(|p| (angle(&station, p), *p))
+            .collect();
+        tmp.sort_by(|&(a1, p1), &(a2, p2)| {
+            a1.partial_cmp(&a2).unwrap()
+                .then_with(|| station.manhattan(p1).cmp(&station.manhattan(p2)))
+        });
+        if tmp.len() < 200 {
+            return Err(format!("Only {} asteroids to vaporize", tmp.len()).into());
+        }
+        let dead = kill(&tmp, 200);
+        answer(dead.x * 100 + dead.y)
+    }
+}
+
+fn kill(asteroids: &[(Angle, Point)], num: usize) -> Point {
+    let mut asteroids = asteroids.to_vec();
+    let mut num_dead = 0;
+    loop {
+        let mut seen = HashSet::new();
+        let mut rest = Vec::new();
+        for (a, p) in asteroids.iter() {
+            if seen.contains(a) {
+                rest.push((*a, *p));
+            } else {
+                num_dead += 1;
+                seen.insert(*a);
+                if num_dead == num {
+                    return *p;
+                }
+            }
+        }
+        asteroids.clear();
+        asteroids.extend(rest);
+        if asteroids.is_empty() {
+            break;
+        }
+    }
+    unreachable!();
+}
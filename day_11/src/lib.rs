@@ -0,0 +1,94 @@
+use grid::{ocr, Bounds, Dense, Direction, Point, Sparse, Turn};
+use intcode::{load, Program, RunState, Word};
+use solution::{answer, Solution};
+
+use std::error::Error;
+
+fn turn(code: Word) -> Result<Turn, String> {
+    match code {
+        0 => Ok(Turn::Left),
+        1 => Ok(Turn::Right),
+        _ => Err(format!("Unknown rotate command: {}", code)),
+    }
+}
+
+/// Runs the robot and returns each panel it paints with the colour, in
+/// order. A panel painted again appears again.
+fn paint_steps(mut program: Program, start_color: Word) -> Result<Vec<(Point, Word)>, Box<dyn Error>> {
+    let mut position = Point::ORIGIN;
+    let mut direction = Direction::Up;
+    let mut painted = Sparse::new();
+    let mut steps = Vec::new();
+    let mut color = start_color;
+    while let RunState::Output(new_color) = program.run_partial(&mut || Some(color))? {
+        painted.insert(position, new_color);
+        steps.push((position, new_color));
+        match program.run_partial(&mut || Some(color))? {
+            RunState::Output(rotate_command) => {
+                direction = direction.rotate(turn(rotate_command)?);
+                position = direction.move_one(position);
+            }
+            _ => break,
+        }
+        color = *painted.get(position).unwrap_or(&0);
+    }
+    Ok(steps)
+}
+
+fn paint(program: Program, start_color: Word) -> Result<Sparse<Word>, Box<dyn Error>> {
+    Ok(paint_steps(program, start_color)?.into_iter().collect())
+}
+
+fn read_registration(colors: &Sparse<Word>) -> Result<String, Box<dyn Error>> {
+    let bounds = colors.bounds().ok_or("Nothing was painted")?;
+    Ok(ocr::read(&colors.to_dense(bounds, |color| color == Some(&1)))?)
+}
+
+/// The hull as the registration is painted, one frame per painted panel,
+/// in palette indices: 0 black, 1 white.
+pub fn frames(input: &str) -> Result<Vec<Dense<u8>>, Box<dyn Error>> {
+    let code = load::from_str(input)?;
+    let steps = paint_steps(Program::new(&code), 1)?;
+    let bounds = Bounds::of(steps.iter().map(|&(p, _)| p)).ok_or("Nothing was painted")?;
+    let mut hull = Sparse::new();
+    Ok(steps
+        .into_iter()
+        .map(|(p, color)| {
+            hull.insert(p, color);
+            hull.to_dense(bounds, |color| if color == Some(&1) { 1 } else { 0 })
+        })
+        .collect())
+}
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    fn day(&self) -> u32 {
+        11
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        let code = load::from_str(input)?;
+        answer(paint(Program::new(&code), 0)?.len())
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        let code = load::from_str(input)?;
+        answer(read_registration(&paint(Program::new(&code), 1)?)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_registration_rows() {
+        // Rows are y, growing down, as `Direction::Up` moves the robot.
+        // A transposed hull would not read as a letter.
+        let text = "#...\n#...\n#...\n#...\n#...\n####\n";
+        let hull = Sparse::parse(text, |c| Some(if c == '#' { 1 } else { 0 }));
+        assert_eq!(read_registration(&hull).unwrap(), "L");
+        assert_eq!(Direction::Up.move_one(Point::ORIGIN), Point::new(0, -1));
+    }
+}
@@ -0,0 +1,386 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::thread;
+
+use solution::{answer, Solution};
+
+pub mod series;
+
+/// The positions and velocities of all bodies along one axis.
+///
+/// Gravity along an axis only depends on positions along that axis, so
+/// each axis can be simulated on its own.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Axis {
+    positions: Vec<i64>,
+    velocities: Vec<i64>,
+}
+
+impl Axis {
+    fn new(positions: Vec<i64>) -> Axis {
+        let velocities = vec![0; positions.len()];
+        Axis {
+            positions,
+            velocities,
+        }
+    }
+
+    fn step(&mut self) {
+        let n = self.positions.len();
+        for i in 0..n {
+            for j in i + 1..n {
+                let pull = match self.positions[i].cmp(&self.positions[j]) {
+                    Ordering::Less => 1,
+                    Ordering::Greater => -1,
+                    Ordering::Equal => 0,
+                };
+                self.velocities[i] += pull;
+                self.velocities[j] -= pull;
+            }
+        }
+        for (position, velocity) in self.positions.iter_mut().zip(&self.velocities) {
+            *position += velocity;
+        }
+    }
+
+    fn cycle(&self) -> Cycle {
+        find_cycle(self, Axis::step)
+    }
+}
+
+/// Where a sequence of states starts repeating: state `start + period` is
+/// the first that equals an earlier one, state `start`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Cycle {
+    start: u128,
+    period: u128,
+}
+
+/// Finds the cycle of the states reached from `first` by `step`, with
+/// Brent's algorithm. It keeps only two states, so it works for cycles
+/// that start after any number of steps.
+fn find_cycle<T: Clone + PartialEq, F: Fn(&mut T)>(first: &T, step: F) -> Cycle {
+    // Find the period: the hare runs ahead, and the tortoise jumps to it
+    // at every power of two until the hare laps it.
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = first.clone();
+    let mut hare = first.clone();
+    step(&mut hare);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        step(&mut hare);
+        period += 1;
+    }
+
+    // With the hare a period ahead, they meet where the cycle starts.
+    let mut tortoise = first.clone();
+    let mut hare = first.clone();
+    for _ in 0..period {
+        step(&mut hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        step(&mut tortoise);
+        step(&mut hare);
+        start += 1;
+    }
+    Cycle { start, period }
+}
+
+/// Bodies pulling on each other in any number of dimensions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct System {
+    axes: Vec<Axis>,
+    /// The name of each axis, like `x`.
+    names: Vec<String>,
+}
+
+/// `x`, `y` and `z` for up to three dimensions, else `d0`, `d1` and so on.
+fn default_names(dimensions: usize) -> Vec<String> {
+    if dimensions <= 3 {
+        ["x", "y", "z"][..dimensions]
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    } else {
+        (0..dimensions).map(|d| format!("d{}", d)).collect()
+    }
+}
+
+impl System {
+    /// A system of bodies at rest. Each position has a coordinate per
+    /// dimension.
+    fn new(positions: &[Vec<i64>]) -> System {
+        let dimensions = positions.first().map_or(0, |p| p.len());
+        let axes = (0..dimensions)
+            .map(|d| Axis::new(positions.iter().map(|p| p[d]).collect()))
+            .collect();
+        let names = default_names(dimensions);
+        System { axes, names }
+    }
+
+    fn bodies(&self) -> usize {
+        self.axes.first().map_or(0, |axis| axis.positions.len())
+    }
+
+    fn step(&mut self) {
+        for axis in self.axes.iter_mut() {
+            axis.step();
+        }
+    }
+
+    /// The potential and kinetic energy of a body: the sums of the
+    /// absolute values of its position and of its velocity.
+    fn energy(&self, body: usize) -> (i64, i64) {
+        let potential = self.axes.iter().map(|a| a.positions[body].abs()).sum();
+        let kinetic = self.axes.iter().map(|a| a.velocities[body].abs()).sum();
+        (potential, kinetic)
+    }
+
+    /// The sum over the bodies of potential times kinetic energy.
+    fn total_energy(&self) -> i64 {
+        (0..self.bodies())
+            .map(|i| {
+                let (potential, kinetic) = self.energy(i);
+                potential * kinetic
+            })
+            .sum()
+    }
+
+    /// The cycle of the whole system, found for each axis on its own
+    /// thread. The system repeats once every axis is in its cycle, with the
+    /// least common multiple of their periods. `None` if that does not fit
+    /// a `u128`.
+    fn cycle(&self) -> Option<Cycle> {
+        let cycles: Vec<Cycle> = thread::scope(|scope| {
+            let threads: Vec<_> = self
+                .axes
+                .iter()
+                .map(|axis| scope.spawn(move || axis.cycle()))
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect()
+        });
+        cycles.iter().try_fold(
+            Cycle {
+                start: 0,
+                period: 1,
+            },
+            |system, axis| {
+                Some(Cycle {
+                    start: system.start.max(axis.start),
+                    period: lcm(system.period, axis.period)?,
+                })
+            },
+        )
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    let mut a = a;
+    let mut b = b;
+    while b != 0 {
+        let tmp = b;
+        b = a % b;
+        a = tmp;
+    }
+    a
+}
+
+/// The least common multiple, or `None` if it overflows.
+fn lcm(a: u128, b: u128) -> Option<u128> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+/// Parses one body per line, like `<x=-1, y=0, z=2>`. Any number of
+/// coordinates can be given, as long as every body has as many. The axes
+/// take their names from the first body; coordinates without a name get
+/// the default one.
+fn parse(input: &str) -> Result<System, Box<dyn Error>> {
+    let mut positions: Vec<Vec<i64>> = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let inner = line
+            .strip_prefix('<')
+            .and_then(|line| line.strip_suffix('>'))
+            .ok_or_else(|| format!("Line {}: expected <x=.., y=.., ..>", i + 1))?;
+        let coordinates: Vec<_> = inner
+            .split(',')
+            .map(|coordinate| match coordinate.split_once('=') {
+                Some((name, value)) => (Some(name.trim()), value),
+                None => (None, coordinate),
+            })
+            .collect();
+        let defaults = default_names(coordinates.len());
+        let line_names = coordinates
+            .iter()
+            .zip(defaults)
+            .map(|(&(name, _), default)| match name {
+                Some(name) if name.is_empty() || !name.chars().all(char::is_alphanumeric) => {
+                    Err(format!("Line {}: invalid axis name {:?}", i + 1, name))
+                }
+                Some(name) => Ok(name.to_string()),
+                None => Ok(default),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for (d, name) in line_names.iter().enumerate() {
+            if line_names[..d].contains(name) {
+                return Err(format!("Line {}: axis {} appears twice", i + 1, name).into());
+            }
+        }
+        let position = coordinates
+            .iter()
+            .map(|(_, value)| {
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Line {}: invalid coordinate {:?}", i + 1, value.trim()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(first) = positions.first() {
+            if first.len() != position.len() {
+                return Err(format!(
+                    "Line {}: {} coordinates, expected {}",
+                    i + 1,
+                    position.len(),
+                    first.len()
+                )
+                .into());
+            }
+            if names != line_names {
+                return Err(format!(
+                    "Line {}: axes {}, expected {}",
+                    i + 1,
+                    line_names.join(", "),
+                    names.join(", ")
+                )
+                .into());
+            }
+        } else {
+            names = line_names;
+        }
+        positions.push(position);
+    }
+    if positions.is_empty() {
+        return Err("No bodies".into());
+    }
+    let mut system = System::new(&positions);
+    system.names = names;
+    Ok(system)
+}
+
+pub struct Day12;
+
+impl Solution for Day12 {
+    fn day(&self) -> u32 {
+        12
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        let mut system = parse(input)?;
+        for _ in 0..1000 {
+            system.step();
+        }
+        answer(system.total_energy())
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        let cycle = parse(input)?.cycle();
+        let repeat = cycle.and_then(|cycle| cycle.start.checked_add(cycle.period));
+        answer(repeat.ok_or("The cycle does not fit in 128 bits")?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> System {
+        System::new(&[
+            vec![-1, 0, 2],
+            vec![2, -10, -7],
+            vec![4, -8, 8],
+            vec![3, 5, -1],
+        ])
+    }
+
+    #[test]
+    fn test_energy() {
+        let mut system = example();
+        for _ in 0..10 {
+            system.step();
+        }
+        assert_eq!(system.total_energy(), 179);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let cycle = example().cycle().unwrap();
+        assert_eq!((cycle.start, cycle.period), (0, 2772));
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        // 3, 10, 101, 2, 5, 26, 167, 95, 101, ...
+        let cycle = find_cycle(&3u32, |x| *x = (*x * *x + 1) % 255);
+        assert_eq!((cycle.start, cycle.period), (2, 6));
+        let cycle = find_cycle(&0u32, |x| *x = (*x + 1) % 5);
+        assert_eq!((cycle.start, cycle.period), (0, 5));
+        let cycle = find_cycle(&7u32, |_| {});
+        assert_eq!((cycle.start, cycle.period), (0, 1));
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let system = parse("<x=-1, y=0, z=2, w=5>\n<x=2, y=-10, z=-7, w=0>\n").unwrap();
+        assert_eq!((system.bodies(), system.axes.len()), (2, 4));
+        let periods: Vec<_> = system.axes.iter().map(|a| a.cycle().period).collect();
+        let expected = periods.iter().fold(1, |p, &q| lcm(p, q).unwrap());
+        assert_eq!(system.cycle().unwrap().period, expected);
+
+        let line = System::new(&[vec![0], vec![3], vec![5]]);
+        assert_eq!(line.cycle(), Some(line.axes[0].cycle()));
+        assert!(parse("<x=1, y=2>\n<x=1>").is_err());
+        assert!(parse("<x=a>").is_err());
+        assert!(parse("x=1").is_err());
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(example().names, ["x", "y", "z"]);
+        assert_eq!(System::new(&[vec![0; 4]]).names, ["d0", "d1", "d2", "d3"]);
+        let system = parse("<a=1, b=2, 3>\n<a=0, b=5, 7>").unwrap();
+        assert_eq!(system.names, ["a", "b", "z"]);
+        assert_eq!(system.energy(1), (12, 0));
+        assert!(parse("<a=1, b=2>\n<b=0, a=5>").is_err());
+        assert!(parse("<x\"=1>").is_err());
+        // Default names count too, so the columns of a series stay apart.
+        assert!(parse("<x=1, x=2>").is_err());
+        assert!(parse("<y=1, 2>").is_err());
+        assert_eq!(parse("<y=1, x=2>").unwrap().names, ["y", "x"]);
+    }
+
+    #[test]
+    fn test_lcm() {
+        assert_eq!(lcm(4, 6), Some(12));
+        // Past what a usize multiply could hold, but well within u128.
+        let big = u64::MAX as u128;
+        assert_eq!(lcm(big, big - 1), Some(big * (big - 1)));
+        assert_eq!(lcm(u128::MAX, u128::MAX - 1), None);
+        assert_eq!(lcm(u128::MAX, u128::MAX), Some(u128::MAX));
+    }
+}
@@ -0,0 +1,179 @@
+//! Recording a simulation step by step, to plot the orbits and energies
+//! of the bodies with other tools.
+
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::System;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// A header and then a row per body and step.
+    Csv,
+    /// A JSON object per body and step, one per line.
+    Ndjson,
+}
+
+impl Format {
+    /// The format for the extension of `path`: `.csv`, or `.ndjson` or
+    /// `.jsonl`.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(Format::Csv),
+            "ndjson" | "jsonl" => Some(Format::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+/// A simulation to record: the bodies at step 0 and after every `stride`
+/// steps, up to `steps`.
+pub struct Series {
+    system: System,
+    steps: u64,
+    stride: u64,
+}
+
+impl Series {
+    pub fn new(input: &str, steps: u64, stride: u64) -> Result<Series, Box<dyn Error>> {
+        if stride == 0 {
+            return Err("The stride must be at least 1".into());
+        }
+        let system = crate::parse(input)?;
+        Ok(Series {
+            system,
+            steps,
+            stride,
+        })
+    }
+
+    /// Runs the simulation and writes a record for each body at each
+    /// recorded step, with its position, velocity and energies. The total
+    /// energy is potential times kinetic energy.
+    pub fn write(&self, format: Format, out: &mut dyn Write) -> io::Result<()> {
+        let mut system = self.system.clone();
+        if format == Format::Csv {
+            let velocities: Vec<_> = system.names.iter().map(|n| format!("v{}", n)).collect();
+            writeln!(
+                out,
+                "step,body,{},{},potential,kinetic,total",
+                system.names.join(","),
+                velocities.join(",")
+            )?;
+        }
+        for step in 0..=self.steps {
+            if step % self.stride == 0 {
+                for body in 0..system.bodies() {
+                    match format {
+                        Format::Csv => write_csv(&system, step, body, out)?,
+                        Format::Ndjson => write_json(&system, step, body, out)?,
+                    }
+                }
+            }
+            if step < self.steps {
+                system.step();
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_csv(system: &System, step: u64, body: usize, out: &mut dyn Write) -> io::Result<()> {
+    let (potential, kinetic) = system.energy(body);
+    write!(out, "{},{}", step, body)?;
+    for axis in &system.axes {
+        write!(out, ",{}", axis.positions[body])?;
+    }
+    for axis in &system.axes {
+        write!(out, ",{}", axis.velocities[body])?;
+    }
+    writeln!(out, ",{},{},{}", potential, kinetic, potential * kinetic)
+}
+
+/// Writes a JSON line by hand. Axis names are alphanumeric, so nothing
+/// needs escaping.
+fn write_json(system: &System, step: u64, body: usize, out: &mut dyn Write) -> io::Result<()> {
+    let (potential, kinetic) = system.energy(body);
+    let vector = |values: &dyn Fn(usize) -> i64| {
+        let fields: Vec<_> = system
+            .names
+            .iter()
+            .enumerate()
+            .map(|(d, name)| format!("\"{}\":{}", name, values(d)))
+            .collect();
+        format!("{{{}}}", fields.join(","))
+    };
+    writeln!(
+        out,
+        "{{\"step\":{},\"body\":{},\"position\":{},\"velocity\":{},\
+         \"potential\":{},\"kinetic\":{},\"total\":{}}}",
+        step,
+        body,
+        vector(&|d| system.axes[d].positions[body]),
+        vector(&|d| system.axes[d].velocities[body]),
+        potential,
+        kinetic,
+        potential * kinetic
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str =
+        "<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>\n";
+
+    fn write(steps: u64, stride: u64, format: Format) -> String {
+        let mut out = Vec::new();
+        let series = Series::new(EXAMPLE, steps, stride).unwrap();
+        series.write(format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = write(10, 10, Format::Csv);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[0], "step,body,x,y,z,vx,vy,vz,potential,kinetic,total");
+        assert_eq!(lines[1], "0,0,-1,0,2,0,0,0,3,0,0");
+        assert_eq!(lines[5], "10,0,2,1,-3,-3,-2,1,6,6,36");
+        let total: i64 = lines[5..]
+            .iter()
+            .map(|line| line.rsplit(',').next().unwrap().parse::<i64>().unwrap())
+            .sum();
+        assert_eq!(total, 179);
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let json = write(1, 1, Format::Ndjson);
+        assert_eq!(json.lines().count(), 8);
+        assert_eq!(
+            json.lines().nth(4).unwrap(),
+            "{\"step\":1,\"body\":0,\"position\":{\"x\":2,\"y\":-1,\"z\":1},\
+             \"velocity\":{\"x\":3,\"y\":-1,\"z\":-1},\"potential\":4,\"kinetic\":5,\"total\":20}"
+        );
+    }
+
+    #[test]
+    fn test_stride() {
+        let csv = write(10, 3, Format::Csv);
+        let steps: Vec<_> = csv
+            .lines()
+            .skip(1)
+            .step_by(4)
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        assert_eq!(steps, ["0", "3", "6", "9"]);
+        assert!(Series::new(EXAMPLE, 10, 0).is_err());
+        assert_eq!(
+            Format::from_path(Path::new("out/a.JSONL")),
+            Some(Format::Ndjson)
+        );
+        assert_eq!(Format::from_path(Path::new("a.txt")), None);
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    convert::TryFrom,
+    error::Error,
+};
+use grid::{Bounds, Dense, Point, Sparse};
+use grid::image::{Palette, Rgb};
+use intcode::{load, Intcode, Program, RunState, Word};
+use solution::{answer, Solution};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Tile {
+    Empty,
+    Wall,
+    Block,
+    Paddle,
+    Ball
+}
+
+impl TryFrom<Word> for Tile {
+    type Error = String;
+
+    fn try_from(v: Word) -> Result<Tile, String> {
+        use Tile::*;
+        match v {
+            0 => Ok(Empty),
+            1 => Ok(Wall),
+            2 => Ok(Block),
+            3 => Ok(Paddle),
+            4 => Ok(Ball),
+            _ => Err(format!("Unknown tile id {}", v)),
+        }
+    }
+}
+
+/// The screen position output as `x` and `y`.
+// `Word` is wider than `i64` with intcode's `i128` feature.
+#[allow(clippy::unnecessary_cast)]
+fn point(x: Word, y: Word) -> Point {
+    Point::new(x as i64, y as i64)
+}
+
+fn part1(code: Vec<Word>) -> Result<usize, Box<dyn Error>> {
+    let mut code = code;
+    let mut outputs = Vec::new();
+    code.run(&[], &mut |out| outputs.push(out))?;
+    let mut count = 0;
+    for output in outputs.chunks_exact(3) {
+        if Tile::try_from(output[2])? == Tile::Block {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Draws the screen, for watching the game while debugging.
+#[allow(dead_code)]
+fn render(screen: &Sparse<Tile>, score: Word) -> String {
+    let tiles = screen.render(|tile| match tile.unwrap_or(&Tile::Empty) {
+        Tile::Empty => ' ',
+        Tile::Wall => '|',
+        Tile::Block => '#',
+        Tile::Paddle => '_',
+        Tile::Ball => 'o',
+    });
+    format!("Score: {}\n{}", score, tiles)
+}
+
+/// Moves the paddle towards the ball, if both are on the screen.
+fn joystick(screen: &Sparse<Tile>) -> Option<Word> {
+    let ball_pos = screen.find(|&t| t == Tile::Ball)?;
+    let paddle_pos = screen.find(|&t| t == Tile::Paddle)?;
+    Some(Word::from((ball_pos.x - paddle_pos.x).signum()))
+}
+
+/// Plays the game to the end and returns the final score. `on_score` sees
+/// the screen every time the score is updated.
+fn play(
+    code: Vec<Word>,
+    on_score: &mut dyn FnMut(&Sparse<Tile>, Word),
+) -> Result<Word, Box<dyn Error>> {
+    let mut code = code;
+    *code.first_mut().ok_or("The game has no code")? = 2;
+
+    let mut program = Program::new(&code);
+    let mut screen = Sparse::new();
+    let mut score = 0;
+    let mut outputs = Vec::with_capacity(3);
+    loop {
+        let state = program.run_partial(&mut || joystick(&screen))?;
+        match state {
+            RunState::Output(out) => outputs.push(out),
+            RunState::NeedsInput => {
+                return Err("The game needs input without a ball and a paddle".into())
+            }
+            RunState::Halted => return Ok(score),
+        }
+        if let [x, y, out] = outputs[..] {
+            if x == -1 && y == 0 {
+                score = out;
+                on_score(&screen, out);
+            } else {
+                screen.insert(point(x, y), Tile::try_from(out)?);
+            }
+            outputs.clear();
+            // print!("\x1b[2J\x1b[H{}", render(&screen, score));
+            // std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+}
+
+fn part2(code: Vec<Word>) -> Result<Word, Box<dyn Error>> {
+    play(code, &mut |_, _| {})
+}
+
+/// The screen at every score update, in palette indices: 0 empty, 1 wall,
+/// 2 block, 3 paddle and 4 ball.
+pub fn frames(input: &str) -> Result<Vec<Dense<u8>>, Box<dyn Error>> {
+    let mut screens = Vec::new();
+    play(load::from_str(input)?, &mut |screen, _| screens.push(screen.clone()))?;
+    let corners = screens.iter().filter_map(Sparse::bounds).flat_map(|b| vec![b.min, b.max]);
+    let bounds = Bounds::of(corners).ok_or("The game drew nothing")?;
+    Ok(screens
+        .iter()
+        .map(|screen| screen.to_dense(bounds, |tile| *tile.unwrap_or(&Tile::Empty) as u8))
+        .collect())
+}
+
+/// Colours for the tiles in `frames`.
+pub fn palette() -> Palette {
+    Palette::new(vec![
+        Rgb(0x10, 0x10, 0x20),
+        Rgb(0x80, 0x80, 0x90),
+        Rgb(0x30, 0xa0, 0xe0),
+        Rgb(0xf0, 0xf0, 0xf0),
+        Rgb(0xf0, 0xc0, 0x20),
+    ])
+}
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    fn day(&self) -> u32 {
+        13
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        answer(part1(load::from_str(input)?)?)
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        answer(part2(load::from_str(input)?)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Tile::try_from(4), Ok(Tile::Ball));
+        assert!(Tile::try_from(5).is_err());
+        assert!(part1(vec![104, 0, 104, 0, 104, 7, 99]).is_err());
+        assert!(part2(vec![]).is_err());
+        // Asks for input before drawing anything.
+        assert!(part2(vec![1, 0, 0, 0, 3, 0, 99]).is_err());
+    }
+}
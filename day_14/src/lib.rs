@@ -0,0 +1,64 @@
+pub mod parse;
+pub mod plan;
+pub mod reaction;
+
+use parse::Format;
+use plan::Plan;
+use reaction::Graph;
+use solution::{answer, Solution};
+
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Reads the reactions in `format`, or in the format guessed from the
+/// text if it is not known.
+fn parse(input: &str, format: Option<Format>) -> Result<Graph, Box<dyn Error>> {
+    let format = format.unwrap_or_else(|| Format::detect(input));
+    let reactions = parse::parse(input, format, &["ORE"])?;
+    Ok(Graph::new(&reactions, &["ORE"])?)
+}
+
+fn fuel_plan(input: &str, format: Option<Format>) -> Result<Plan, Box<dyn Error>> {
+    Ok(parse(input, format)?.plan("FUEL", 1, &HashMap::new())?)
+}
+
+/// The reactions that make 1 FUEL, as a table.
+pub fn explain(input: &str, format: Option<Format>) -> Result<String, Box<dyn Error>> {
+    Ok(fuel_plan(input, format)?.table())
+}
+
+/// The reactions that make 1 FUEL, as a Graphviz graph.
+pub fn explain_dot(input: &str, format: Option<Format>) -> Result<String, Box<dyn Error>> {
+    Ok(fuel_plan(input, format)?.dot())
+}
+
+/// Solutions only get the text of their input, so unless they are told
+/// its format it is guessed.
+#[derive(Default)]
+pub struct Day14 {
+    format: Option<Format>,
+}
+
+impl Day14 {
+    pub fn new(format: Option<Format>) -> Day14 {
+        Day14 { format }
+    }
+}
+
+impl Solution for Day14 {
+    fn day(&self) -> u32 {
+        14
+    }
+
+    fn part1(&self, input: &str) -> solution::Result {
+        let ore = parse(input, self.format)?.requirements("FUEL", 1)?;
+        answer(ore.get("ORE").copied().unwrap_or(0))
+    }
+
+    fn part2(&self, input: &str) -> solution::Result {
+        let stock: HashMap<_, _> = vec![("ORE".to_string(), 1_000_000_000_000)]
+            .into_iter()
+            .collect();
+        answer(parse(input, self.format)?.max_producible("FUEL", &stock)?)
+    }
+}
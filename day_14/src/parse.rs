@@ -0,0 +1,504 @@
+//! Reading reactions from the puzzle's text format, or from JSON or TOML
+//! with the `json` and `toml` features.
+//!
+//! The text format has one reaction per line:
+//!
+//! ```text
+//! 7 A, 1 B => 1 C
+//! ```
+//!
+//! The structured formats hold a list of `reactions`, each with `inputs`
+//! and an `output` of `chemical` and `quantity`. In TOML:
+//!
+//! ```toml
+//! [[reactions]]
+//! inputs = [{ chemical = "A", quantity = 7 }, { chemical = "B", quantity = 1 }]
+//! output = { chemical = "C", quantity = 1 }
+//! ```
+//!
+//! `Format::from_path` picks the format from a file name, and
+//! `Format::detect` guesses it from the text when nothing else tells it.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::reaction::{Reaction, Term};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Toml,
+}
+
+impl Format {
+    /// The format for the extension of `path`: `.txt`, `.json` or `.toml`.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "txt" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of `input` when nothing else tells it, from its
+    /// first line that is not blank: JSON if it starts with `{`, TOML if it
+    /// is a comment, a table or a `key = value` pair with a bare key, and
+    /// the text format otherwise. Text lines never start with `#` or `[`,
+    /// and a mistyped one like `10 ORE = 10 A` has a space in its "key", so
+    /// it still gets the text format's error.
+    pub fn detect(input: &str) -> Format {
+        let line = input.lines().map(str::trim).find(|line| !line.is_empty());
+        match line {
+            Some(line) if line.starts_with('{') => Format::Json,
+            Some(line) if line.starts_with('#') || line.starts_with('[') || is_pair(line) => {
+                Format::Toml
+            }
+            _ => Format::Text,
+        }
+    }
+}
+
+/// Whether `line` starts with a TOML bare key and an `=`.
+fn is_pair(line: &str) -> bool {
+    match line.split_once('=') {
+        Some((key, value)) if !value.starts_with('>') => {
+            let key = key.trim();
+            !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        }
+        _ => false,
+    }
+}
+
+/// Where a reaction was read from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Location {
+    /// A line of the text format, from 1.
+    Line(usize),
+    /// An entry of a structured list of reactions, from 1.
+    Entry(usize),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Line(line) => write!(f, "line {}", line),
+            Location::Entry(entry) => write!(f, "reaction {}", entry),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// Text that is not a reaction.
+    Syntax(String),
+    InvalidQuantity(String),
+    ZeroQuantity(String),
+    InvalidChemical(String),
+    /// A chemical made by a second reaction.
+    DuplicateProducer {
+        chemical: String,
+        first: Location,
+    },
+    /// A raw chemical, like ORE, as the output of a reaction.
+    RawOutput(String),
+    /// A structured format this build was compiled without.
+    Unsupported {
+        format: &'static str,
+        feature: &'static str,
+    },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    /// `None` when the error is not about one reaction, or when the message
+    /// of a JSON or TOML parser already says where it is.
+    pub location: Option<Location>,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = self.location {
+            write!(f, "{}: ", location)?;
+        }
+        match &self.kind {
+            ErrorKind::Syntax(message) => write!(f, "{}", message),
+            ErrorKind::InvalidQuantity(quantity) => write!(f, "Invalid quantity {:?}", quantity),
+            ErrorKind::ZeroQuantity(chemical) => write!(f, "Quantity of {} is 0", chemical),
+            ErrorKind::InvalidChemical(chemical) => {
+                write!(f, "Invalid chemical name {:?}", chemical)
+            }
+            ErrorKind::DuplicateProducer { chemical, first } => match first {
+                Location::Line(line) => write!(f, "{} is already made on line {}", chemical, line),
+                Location::Entry(entry) => {
+                    write!(f, "{} is already made by reaction {}", chemical, entry)
+                }
+            },
+            ErrorKind::RawOutput(chemical) => {
+                write!(f, "{} is raw and cannot be made by a reaction", chemical)
+            }
+            ErrorKind::Unsupported { format, feature } => {
+                write!(f, "Reading {} needs the {:?} feature", format, feature)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn error(location: Location, kind: ErrorKind) -> ParseError {
+    ParseError {
+        location: Some(location),
+        kind,
+    }
+}
+
+/// Reads reactions in the given format. `raw` chemicals may not be
+/// outputs.
+pub fn parse(input: &str, format: Format, raw: &[&str]) -> Result<Vec<Reaction>, ParseError> {
+    let reactions = match format {
+        Format::Text => return parse_text(input, raw),
+        Format::Json => from_json(input)?,
+        Format::Toml => from_toml(input)?,
+    };
+    let locations = (1..=reactions.len())
+        .map(Location::Entry)
+        .collect::<Vec<_>>();
+    for (reaction, &location) in reactions.iter().zip(&locations) {
+        check_terms(reaction, location)?;
+    }
+    validate(&reactions, &locations, raw)?;
+    Ok(reactions)
+}
+
+/// Reads the text format.
+pub fn parse_text(input: &str, raw: &[&str]) -> Result<Vec<Reaction>, ParseError> {
+    let mut reactions = Vec::new();
+    let mut locations = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let location = Location::Line(i + 1);
+        reactions.push(parse_line(line).map_err(|kind| error(location, kind))?);
+        locations.push(location);
+    }
+    validate(&reactions, &locations, raw)?;
+    Ok(reactions)
+}
+
+fn parse_line(line: &str) -> Result<Reaction, ErrorKind> {
+    let sides: Vec<_> = line.split("=>").collect();
+    let (inputs, output) = match sides.as_slice() {
+        [inputs, output] => (inputs, output),
+        _ => {
+            return Err(ErrorKind::Syntax(format!(
+                "Expected INPUTS => OUTPUT, found {:?}",
+                line.trim()
+            )))
+        }
+    };
+    let inputs = inputs
+        .split(',')
+        .map(parse_term)
+        .collect::<Result<_, _>>()?;
+    let output = parse_term(output)?;
+    Ok(Reaction { inputs, output })
+}
+
+fn parse_term(spec: &str) -> Result<Term, ErrorKind> {
+    let words: Vec<_> = spec.split_whitespace().collect();
+    let (quantity, chemical) = match words.as_slice() {
+        [quantity, chemical] => (quantity, chemical),
+        [] => return Err(ErrorKind::Syntax("Missing a chemical".to_string())),
+        _ => {
+            return Err(ErrorKind::Syntax(format!(
+                "Expected QUANTITY CHEMICAL, found {:?}",
+                spec.trim()
+            )))
+        }
+    };
+    let quantity = quantity
+        .parse()
+        .map_err(|_| ErrorKind::InvalidQuantity(quantity.to_string()))?;
+    let term = Term::new(chemical, quantity);
+    check_term(&term)?;
+    Ok(term)
+}
+
+fn check_term(term: &Term) -> Result<(), ErrorKind> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    if term.chemical.is_empty() || !term.chemical.chars().all(valid) {
+        return Err(ErrorKind::InvalidChemical(term.chemical.clone()));
+    }
+    if term.quantity == 0 {
+        return Err(ErrorKind::ZeroQuantity(term.chemical.clone()));
+    }
+    Ok(())
+}
+
+fn check_terms(reaction: &Reaction, location: Location) -> Result<(), ParseError> {
+    if reaction.inputs.is_empty() {
+        let message = format!("{} has no inputs", reaction.output.chemical);
+        return Err(error(location, ErrorKind::Syntax(message)));
+    }
+    reaction
+        .inputs
+        .iter()
+        .chain(Some(&reaction.output))
+        .try_for_each(check_term)
+        .map_err(|kind| error(location, kind))
+}
+
+/// Checks that no chemical is made twice and no raw chemical is made.
+fn validate(
+    reactions: &[Reaction],
+    locations: &[Location],
+    raw: &[&str],
+) -> Result<(), ParseError> {
+    let mut producers = HashMap::new();
+    for (reaction, &location) in reactions.iter().zip(locations) {
+        let chemical = &reaction.output.chemical;
+        if raw.contains(&chemical.as_str()) {
+            return Err(error(location, ErrorKind::RawOutput(chemical.clone())));
+        }
+        if let Some(&first) = producers.get(chemical) {
+            let kind = ErrorKind::DuplicateProducer {
+                chemical: chemical.clone(),
+                first,
+            };
+            return Err(error(location, kind));
+        }
+        producers.insert(chemical, location);
+    }
+    Ok(())
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Document {
+    reactions: Vec<Reaction>,
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+fn syntax(message: String) -> ParseError {
+    ParseError {
+        location: None,
+        kind: ErrorKind::Syntax(message),
+    }
+}
+
+#[cfg(feature = "json")]
+fn from_json(input: &str) -> Result<Vec<Reaction>, ParseError> {
+    let document: Document = serde_json::from_str(input).map_err(|e| syntax(e.to_string()))?;
+    Ok(document.reactions)
+}
+
+#[cfg(not(feature = "json"))]
+fn from_json(_input: &str) -> Result<Vec<Reaction>, ParseError> {
+    Err(ParseError {
+        location: None,
+        kind: ErrorKind::Unsupported {
+            format: "JSON",
+            feature: "json",
+        },
+    })
+}
+
+#[cfg(feature = "toml")]
+fn from_toml(input: &str) -> Result<Vec<Reaction>, ParseError> {
+    let document: Document = toml::from_str(input).map_err(|e| syntax(e.to_string()))?;
+    Ok(document.reactions)
+}
+
+#[cfg(not(feature = "toml"))]
+fn from_toml(_input: &str) -> Result<Vec<Reaction>, ParseError> {
+    Err(ParseError {
+        location: None,
+        kind: ErrorKind::Unsupported {
+            format: "TOML",
+            feature: "toml",
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEXT: &str = "\
+10 ORE => 10 A
+1 ORE => 1 B
+
+7 A,  1 B => 1 C
+";
+
+    fn expected() -> Vec<Reaction> {
+        vec![
+            Reaction {
+                inputs: vec![Term::new("ORE", 10)],
+                output: Term::new("A", 10),
+            },
+            Reaction {
+                inputs: vec![Term::new("ORE", 1)],
+                output: Term::new("B", 1),
+            },
+            Reaction {
+                inputs: vec![Term::new("A", 7), Term::new("B", 1)],
+                output: Term::new("C", 1),
+            },
+        ]
+    }
+
+    fn parse_error(input: &str, format: Format) -> String {
+        parse(input, format, &["ORE"]).unwrap_err().to_string()
+    }
+
+    fn text_error(input: &str) -> String {
+        parse_error(input, Format::Text)
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(parse(TEXT, Format::Text, &["ORE"]), Ok(expected()));
+        assert_eq!(parse("", Format::Text, &["ORE"]), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_text_errors() {
+        assert_eq!(
+            text_error("1 ORE => 1 A\n1 ORE -> 1 B"),
+            "line 2: Expected INPUTS => OUTPUT, found \"1 ORE -> 1 B\""
+        );
+        assert_eq!(
+            text_error("1 ORE => 1 A => 2 B"),
+            "line 1: Expected INPUTS => OUTPUT, found \"1 ORE => 1 A => 2 B\""
+        );
+        assert_eq!(text_error("1 ORE, => 1 A"), "line 1: Missing a chemical");
+        assert_eq!(
+            text_error("1 ORE => 1 A B"),
+            "line 1: Expected QUANTITY CHEMICAL, found \"1 A B\""
+        );
+        assert_eq!(text_error("x ORE => 1 A"), "line 1: Invalid quantity \"x\"");
+        assert_eq!(
+            text_error("-1 ORE => 1 A"),
+            "line 1: Invalid quantity \"-1\""
+        );
+        assert_eq!(text_error("1 ORE => 0 A"), "line 1: Quantity of A is 0");
+        assert_eq!(
+            text_error("1 ORE => 1 A:"),
+            "line 1: Invalid chemical name \"A:\""
+        );
+        assert_eq!(
+            text_error("1 ORE => 1 A\n\n2 ORE => 1 A"),
+            "line 3: A is already made on line 1"
+        );
+        assert_eq!(
+            text_error("1 A => 1 ORE"),
+            "line 1: ORE is raw and cannot be made by a reaction"
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        let json = r#"{"reactions": [
+            {"inputs": [{"chemical": "ORE", "quantity": 10}], "output": {"chemical": "A", "quantity": 10}},
+            {"inputs": [{"chemical": "ORE", "quantity": 1}], "output": {"chemical": "B", "quantity": 1}},
+            {"inputs": [{"chemical": "A", "quantity": 7}, {"chemical": "B", "quantity": 1}],
+             "output": {"chemical": "C", "quantity": 1}}
+        ]}"#;
+        assert_eq!(parse(json, Format::Json, &["ORE"]), Ok(expected()));
+
+        let duplicate = r#"{"reactions": [
+            {"inputs": [{"chemical": "ORE", "quantity": 1}], "output": {"chemical": "A", "quantity": 1}},
+            {"inputs": [{"chemical": "ORE", "quantity": 2}], "output": {"chemical": "A", "quantity": 1}}
+        ]}"#;
+        assert_eq!(
+            parse_error(duplicate, Format::Json),
+            "reaction 2: A is already made by reaction 1"
+        );
+        let empty =
+            r#"{"reactions": [{"inputs": [], "output": {"chemical": "A", "quantity": 1}}]}"#;
+        assert_eq!(
+            parse_error(empty, Format::Json),
+            "reaction 1: A has no inputs"
+        );
+        let error = parse_error("{\"reactions\": [\n{\"inputs\": 3}]}", Format::Json);
+        assert!(error.contains("line 2"), "{}", error);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml() {
+        let toml = r#"
+# Generated
+[[reactions]]
+inputs = [{ chemical = "ORE", quantity = 10 }]
+output = { chemical = "A", quantity = 10 }
+
+[[reactions]]
+inputs = [{ chemical = "ORE", quantity = 1 }]
+output = { chemical = "B", quantity = 1 }
+
+[[reactions]]
+inputs = [{ chemical = "A", quantity = 7 }, { chemical = "B", quantity = 1 }]
+output = { chemical = "C", quantity = 1 }
+"#;
+        assert_eq!(parse(toml, Format::Toml, &["ORE"]), Ok(expected()));
+
+        let raw = r#"
+[[reactions]]
+inputs = [{ chemical = "A", quantity = 1 }]
+output = { chemical = "ORE", quantity = 1 }
+"#;
+        assert_eq!(
+            parse_error(raw, Format::Toml),
+            "reaction 1: ORE is raw and cannot be made by a reaction"
+        );
+        let error = parse_error("[[reactions]]\ninputs = 3\n", Format::Toml);
+        assert!(error.contains("line 2"), "{}", error);
+
+        let bare = r#"
+reactions = [
+    { inputs = [{ chemical = "ORE", quantity = 1 }], output = { chemical = "A", quantity = 1 } },
+]
+"#;
+        let reactions = parse(bare, Format::detect(bare), &["ORE"]).unwrap();
+        assert_eq!(reactions[0].output, Term::new("A", 1));
+    }
+
+    #[cfg(not(feature = "json"))]
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(
+            parse_error("{}", Format::Json),
+            "Reading JSON needs the \"json\" feature"
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Format::detect(TEXT), Format::Text);
+        assert_eq!(Format::detect(""), Format::Text);
+        assert_eq!(Format::detect("\n  {\"reactions\": []}"), Format::Json);
+        assert_eq!(Format::detect("# Reactions\n"), Format::Toml);
+        assert_eq!(Format::detect("[[reactions]]\n"), Format::Toml);
+        assert_eq!(Format::detect("reactions = []\n"), Format::Toml);
+        assert_eq!(Format::detect("10 ORE = 10 A\n"), Format::Text);
+        assert_eq!(Format::detect("A=>B\n"), Format::Text);
+        assert_eq!(
+            Format::from_path(Path::new("in/reactions.TOML")),
+            Some(Format::Toml)
+        );
+        assert_eq!(Format::from_path(Path::new("input")), None);
+    }
+}
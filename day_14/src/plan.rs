@@ -0,0 +1,210 @@
+//! Production plans and ways to show them.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::reaction::{Reaction, Term};
+
+/// A reaction of a plan and how it is used.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Step {
+    pub reaction: Reaction,
+    pub runs: u64,
+    /// The output of all the runs.
+    pub produced: u64,
+    /// The output nothing in the plan consumes.
+    pub leftover: u64,
+}
+
+impl Step {
+    /// The inputs of all the runs.
+    pub fn consumed(&self) -> impl Iterator<Item = Term> + '_ {
+        self.reaction
+            .inputs
+            .iter()
+            .map(move |term| Term::new(&term.chemical, term.quantity * self.runs))
+    }
+}
+
+/// What making a target takes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Plan {
+    pub target: Term,
+    /// The reactions to run, in an order where every reaction comes after
+    /// the reactions making its inputs.
+    pub steps: Vec<Step>,
+    /// Chemicals taken from the stock.
+    pub used: BTreeMap<String, u64>,
+    /// Raw chemicals needed beyond the stock.
+    pub raw: BTreeMap<String, u64>,
+}
+
+fn quantities(terms: &BTreeMap<String, u64>) -> String {
+    let terms: Vec<_> = terms
+        .iter()
+        .map(|(chemical, &quantity)| Term::new(chemical, quantity).to_string())
+        .collect();
+    if terms.is_empty() {
+        "nothing".to_string()
+    } else {
+        terms.join(", ")
+    }
+}
+
+/// Escapes text for a quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl Plan {
+    pub fn step(&self, chemical: &str) -> Option<&Step> {
+        self.steps
+            .iter()
+            .find(|step| step.reaction.output.chemical == chemical)
+    }
+
+    /// Lays out the steps in a table, followed by what the plan takes from
+    /// the stock and the raw chemicals it needs.
+    pub fn table(&self) -> String {
+        let headings = ["#", "Runs", "Produced", "Leftover", "Reaction"];
+        let rows: Vec<[String; 5]> = self
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                [
+                    (i + 1).to_string(),
+                    step.runs.to_string(),
+                    step.produced.to_string(),
+                    step.leftover.to_string(),
+                    step.reaction.to_string(),
+                ]
+            })
+            .collect();
+        let mut widths = headings.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        let mut table = String::new();
+        let mut line = |cells: &[&str]| {
+            let mut text = String::new();
+            for (column, (cell, &width)) in cells.iter().zip(&widths).enumerate() {
+                if column > 0 {
+                    text.push_str("  ");
+                }
+                // The reaction column is text; the others are numbers.
+                if column == 4 {
+                    write!(text, "{:<1$}", cell, width).unwrap();
+                } else {
+                    write!(text, "{:>1$}", cell, width).unwrap();
+                }
+            }
+            table.push_str(text.trim_end());
+            table.push('\n');
+        };
+        line(&headings);
+        for row in &rows {
+            line(&row.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+        if !self.used.is_empty() {
+            writeln!(table, "From stock: {}", quantities(&self.used)).unwrap();
+        }
+        writeln!(table, "Raw: {}", quantities(&self.raw)).unwrap();
+        table
+    }
+
+    /// Draws the reactions as a Graphviz graph, with an edge from each
+    /// input to the chemical made from it, labelled with the quantity
+    /// consumed.
+    pub fn dot(&self) -> String {
+        let mut dot = String::from("digraph plan {\n    rankdir=LR;\n");
+        let mut chemicals: Vec<&str> = self
+            .steps
+            .iter()
+            .flat_map(|step| step.reaction.inputs.iter().map(|t| t.chemical.as_str()))
+            .filter(|chemical| self.step(chemical).is_none())
+            .collect();
+        chemicals.sort_unstable();
+        chemicals.dedup();
+        for chemical in chemicals {
+            let used = self.used.get(chemical).copied().unwrap_or(0);
+            let needed = used + self.raw.get(chemical).copied().unwrap_or(0);
+            writeln!(
+                dot,
+                "    \"{0}\" [shape=box, label=\"{0}\\n{1}\"];",
+                escape(chemical),
+                needed
+            )
+            .unwrap();
+        }
+        for step in &self.steps {
+            let output = &step.reaction.output.chemical;
+            writeln!(
+                dot,
+                "    \"{0}\" [label=\"{0}\\n{1} x {2}\\nleftover {3}\"];",
+                escape(output),
+                step.runs,
+                step.reaction.output.quantity,
+                step.leftover
+            )
+            .unwrap();
+            for input in step.consumed() {
+                writeln!(
+                    dot,
+                    "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                    escape(&input.chemical),
+                    escape(output),
+                    input.quantity
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::Format;
+    use crate::reaction::Graph;
+
+    use std::collections::HashMap;
+
+    fn plan() -> Plan {
+        let input = "10 ORE => 10 A\n1 ORE => 1 B\n7 A, 1 B => 1 C\n7 A, 1 C => 1 FUEL\n";
+        let reactions = crate::parse::parse(input, Format::Text, &["ORE"]).unwrap();
+        let graph = Graph::new(&reactions, &["ORE"]).unwrap();
+        graph.plan("FUEL", 1, &HashMap::new()).unwrap()
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            plan().table(),
+            "\
+#  Runs  Produced  Leftover  Reaction
+1     2        20         6  10 ORE => 10 A
+2     1         1         0  1 ORE => 1 B
+3     1         1         0  7 A, 1 B => 1 C
+4     1         1         0  7 A, 1 C => 1 FUEL
+Raw: 21 ORE
+"
+        );
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = plan().dot();
+        assert!(dot.starts_with("digraph plan {\n"));
+        assert!(dot.contains("    \"ORE\" [shape=box, label=\"ORE\\n21\"];\n"));
+        assert!(dot.contains("    \"A\" [label=\"A\\n2 x 10\\nleftover 6\"];\n"));
+        assert!(dot.contains("    \"A\" -> \"FUEL\" [label=\"7\"];\n"));
+        assert!(dot.contains("    \"ORE\" -> \"A\" [label=\"20\"];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
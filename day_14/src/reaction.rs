@@ -0,0 +1,439 @@
+//! A graph of chemical reactions, solved in topological order.
+//!
+//! Every chemical is either raw, like ORE, or made by exactly one reaction.
+//! Reactions run whole, so making a chemical can leave some of it over.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::plan::{Plan, Step};
+
+/// A quantity of a chemical.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(feature = "json", feature = "toml"), derive(serde::Deserialize))]
+pub struct Term {
+    pub chemical: String,
+    pub quantity: u64,
+}
+
+impl Term {
+    pub fn new(chemical: &str, quantity: u64) -> Term {
+        Term {
+            chemical: chemical.to_string(),
+            quantity,
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.quantity, self.chemical)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(feature = "json", feature = "toml"), derive(serde::Deserialize))]
+pub struct Reaction {
+    pub inputs: Vec<Term>,
+    pub output: Term,
+}
+
+impl fmt::Display for Reaction {
+    /// Writes the reaction in the puzzle's text format.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, input) in self.inputs.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", input)?;
+        }
+        write!(f, " => {}", self.output)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// A chemical made by more than one reaction.
+    DuplicateProducer(String),
+    /// A reaction that makes none of its output.
+    ZeroOutput(String),
+    /// A chemical that is consumed but neither raw nor made by a reaction.
+    MissingProducer { chemical: String, consumer: String },
+    /// Reactions that need their own output, each consuming the next and
+    /// the last consuming the first.
+    Cycle(Vec<String>),
+    /// A chemical that is neither raw nor made by a reaction.
+    Unreachable(String),
+    /// A quantity too large for `u64`.
+    Overflow,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DuplicateProducer(chemical) => {
+                write!(f, "{} is made by more than one reaction", chemical)
+            }
+            Error::ZeroOutput(chemical) => write!(f, "A reaction makes 0 {}", chemical),
+            Error::MissingProducer { chemical, consumer } => write!(
+                f,
+                "Nothing makes {}, which is needed to make {}",
+                chemical, consumer
+            ),
+            Error::Cycle(chemicals) => {
+                write!(f, "Reactions form a cycle: {}", chemicals.join(" <- "))?;
+                write!(f, " <- {}", chemicals[0])
+            }
+            Error::Unreachable(chemical) => write!(f, "There is no way to make {}", chemical),
+            Error::Overflow => write!(f, "Quantities are too large"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Clone, Debug)]
+struct Recipe {
+    output: u64,
+    /// Input chemicals by index, with quantities.
+    inputs: Vec<(usize, u64)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Graph {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    /// The reaction that makes each chemical; `None` for raw chemicals.
+    recipes: Vec<Option<Recipe>>,
+    /// Every chemical, each before the chemicals its reaction consumes.
+    order: Vec<usize>,
+}
+
+impl Graph {
+    /// Builds the graph of `reactions`, with `raw` chemicals that are not
+    /// made but taken as they are.
+    pub fn new(reactions: &[Reaction], raw: &[&str]) -> Result<Graph, Error> {
+        let mut graph = Graph {
+            names: Vec::new(),
+            index: HashMap::new(),
+            recipes: Vec::new(),
+            order: Vec::new(),
+        };
+        for chemical in raw {
+            graph.intern(chemical);
+        }
+        for reaction in reactions {
+            let output = graph.intern(&reaction.output.chemical);
+            if reaction.output.quantity == 0 {
+                return Err(Error::ZeroOutput(reaction.output.chemical.clone()));
+            }
+            if graph.recipes[output].is_some() {
+                return Err(Error::DuplicateProducer(reaction.output.chemical.clone()));
+            }
+            let inputs = reaction
+                .inputs
+                .iter()
+                .map(|term| (graph.intern(&term.chemical), term.quantity))
+                .collect();
+            graph.recipes[output] = Some(Recipe {
+                output: reaction.output.quantity,
+                inputs,
+            });
+        }
+
+        let raw: Vec<usize> = raw.iter().map(|chemical| graph.index[*chemical]).collect();
+        for (consumer, recipe) in graph.recipes.iter().enumerate() {
+            let inputs = recipe.iter().flat_map(|recipe| recipe.inputs.iter());
+            for &(input, _) in inputs {
+                if graph.recipes[input].is_none() && !raw.contains(&input) {
+                    return Err(Error::MissingProducer {
+                        chemical: graph.names[input].clone(),
+                        consumer: graph.names[consumer].clone(),
+                    });
+                }
+            }
+        }
+        graph.order = graph.topological_order()?;
+        Ok(graph)
+    }
+
+    fn intern(&mut self, chemical: &str) -> usize {
+        if let Some(&i) = self.index.get(chemical) {
+            return i;
+        }
+        self.names.push(chemical.to_string());
+        self.recipes.push(None);
+        self.index
+            .insert(chemical.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    /// Orders the chemicals so that each comes before everything its
+    /// reaction consumes, or finds a cycle.
+    fn topological_order(&self) -> Result<Vec<usize>, Error> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            New,
+            Open,
+            Done,
+        }
+
+        let mut marks = vec![Mark::New; self.names.len()];
+        let mut finished = Vec::with_capacity(self.names.len());
+        for start in 0..self.names.len() {
+            if marks[start] != Mark::New {
+                continue;
+            }
+            // Each entry is a chemical and the number of its inputs visited.
+            let mut path = vec![(start, 0)];
+            marks[start] = Mark::Open;
+            while let Some(&(chemical, next)) = path.last() {
+                let inputs = self.recipes[chemical]
+                    .as_ref()
+                    .map_or(&[][..], |r| &r.inputs[..]);
+                match inputs.get(next) {
+                    Some(&(input, _)) => {
+                        path.last_mut().unwrap().1 += 1;
+                        match marks[input] {
+                            Mark::New => {
+                                marks[input] = Mark::Open;
+                                path.push((input, 0));
+                            }
+                            Mark::Open => {
+                                let from = path.iter().position(|&(c, _)| c == input).unwrap();
+                                let cycle = path[from..]
+                                    .iter()
+                                    .map(|&(c, _)| self.names[c].clone())
+                                    .collect();
+                                return Err(Error::Cycle(cycle));
+                            }
+                            Mark::Done => {}
+                        }
+                    }
+                    None => {
+                        marks[chemical] = Mark::Done;
+                        finished.push(chemical);
+                        path.pop();
+                    }
+                }
+            }
+        }
+        // Inputs finish before the chemicals made from them.
+        finished.reverse();
+        Ok(finished)
+    }
+
+    /// Plans making `quantity` of `target`, taking what is in `stock`
+    /// before running reactions.
+    pub fn plan(
+        &self,
+        target: &str,
+        quantity: u64,
+        stock: &HashMap<String, u64>,
+    ) -> Result<Plan, Error> {
+        let target = *self
+            .index
+            .get(target)
+            .ok_or_else(|| Error::Unreachable(target.to_string()))?;
+        let mut need = vec![0u64; self.names.len()];
+        need[target] = quantity;
+        let mut plan = Plan {
+            target: Term::new(&self.names[target], quantity),
+            steps: Vec::new(),
+            used: BTreeMap::new(),
+            raw: BTreeMap::new(),
+        };
+        for &chemical in &self.order {
+            let name = &self.names[chemical];
+            let available = stock.get(name).copied().unwrap_or(0);
+            let taken = need[chemical].min(available);
+            if taken > 0 {
+                plan.used.insert(name.clone(), taken);
+            }
+            let missing = need[chemical] - taken;
+            if missing == 0 {
+                continue;
+            }
+            match &self.recipes[chemical] {
+                Some(recipe) => {
+                    let runs = missing.div_ceil(recipe.output);
+                    let made = runs.checked_mul(recipe.output).ok_or(Error::Overflow)?;
+                    plan.steps.push(Step {
+                        reaction: self.reaction(chemical, recipe),
+                        runs,
+                        produced: made,
+                        leftover: made - missing,
+                    });
+                    for &(input, each) in &recipe.inputs {
+                        let amount = runs.checked_mul(each).ok_or(Error::Overflow)?;
+                        need[input] = need[input].checked_add(amount).ok_or(Error::Overflow)?;
+                    }
+                }
+                None => {
+                    plan.raw.insert(name.clone(), missing);
+                }
+            }
+        }
+        // Chemicals were visited consumers first.
+        plan.steps.reverse();
+        Ok(plan)
+    }
+
+    fn reaction(&self, output: usize, recipe: &Recipe) -> Reaction {
+        Reaction {
+            inputs: recipe
+                .inputs
+                .iter()
+                .map(|&(input, quantity)| Term::new(&self.names[input], quantity))
+                .collect(),
+            output: Term::new(&self.names[output], recipe.output),
+        }
+    }
+
+    /// The raw chemicals needed to make `quantity` of `target` from
+    /// nothing.
+    pub fn requirements(
+        &self,
+        target: &str,
+        quantity: u64,
+    ) -> Result<BTreeMap<String, u64>, Error> {
+        Ok(self.plan(target, quantity, &HashMap::new())?.raw)
+    }
+
+    /// Finds the most of `target` that can be made from `stock`.
+    pub fn max_producible(&self, target: &str, stock: &HashMap<String, u64>) -> Result<u64, Error> {
+        let feasible = |quantity| match self.plan(target, quantity, stock) {
+            Ok(plan) => Ok(plan.raw.is_empty()),
+            Err(Error::Overflow) => Ok(false),
+            Err(e) => Err(e),
+        };
+        // Every reaction makes something and needs inputs that come from
+        // raw chemicals in the end, so the search is bounded.
+        let mut high = 1;
+        while feasible(high)? {
+            high = high.checked_mul(2).ok_or(Error::Overflow)?;
+        }
+        let mut low = high / 2;
+        // `low` is feasible (or 0) and `high` is not.
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if feasible(mid)? {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn reaction(inputs: &[(u64, &str)], output: (u64, &str)) -> Reaction {
+        Reaction {
+            inputs: inputs.iter().map(|&(q, c)| Term::new(c, q)).collect(),
+            output: Term::new(output.1, output.0),
+        }
+    }
+
+    fn example() -> Graph {
+        let reactions = vec![
+            reaction(&[(10, "ORE")], (10, "A")),
+            reaction(&[(1, "ORE")], (1, "B")),
+            reaction(&[(7, "A"), (1, "B")], (1, "C")),
+            reaction(&[(7, "A"), (1, "C")], (1, "D")),
+            reaction(&[(7, "A"), (1, "D")], (1, "E")),
+            reaction(&[(7, "A"), (1, "E")], (1, "FUEL")),
+        ];
+        Graph::new(&reactions, &["ORE"]).unwrap()
+    }
+
+    #[test]
+    fn test_plan() {
+        let graph = example();
+        let ore = graph.requirements("FUEL", 1).unwrap();
+        assert_eq!(
+            ore.into_iter().collect::<Vec<_>>(),
+            vec![("ORE".to_string(), 31)]
+        );
+
+        let plan = graph.plan("FUEL", 1, &HashMap::new()).unwrap();
+        assert_eq!(plan.step("A").unwrap().runs, 3);
+        assert_eq!(plan.step("A").unwrap().leftover, 2);
+        assert_eq!(plan.step("FUEL").unwrap().runs, 1);
+        assert_eq!(
+            plan.steps.last().unwrap().reaction.output,
+            Term::new("FUEL", 1)
+        );
+
+        let stock: HashMap<_, _> = vec![("A".to_string(), 28), ("ORE".to_string(), 5)]
+            .into_iter()
+            .collect();
+        let plan = graph.plan("FUEL", 1, &stock).unwrap();
+        assert_eq!(plan.used["A"], 28);
+        assert_eq!(plan.used["ORE"], 1);
+        assert!(plan.raw.is_empty());
+        assert_eq!(graph.requirements("ORE", 5).unwrap()["ORE"], 5);
+    }
+
+    #[test]
+    fn test_max_producible() {
+        let graph = example();
+        let stock = |ore| vec![("ORE".to_string(), ore)].into_iter().collect();
+        assert_eq!(graph.max_producible("FUEL", &stock(30)), Ok(0));
+        assert_eq!(graph.max_producible("FUEL", &stock(31)), Ok(1));
+        assert_eq!(graph.max_producible("B", &stock(1000)), Ok(1000));
+        assert_eq!(graph.max_producible("FUEL", &HashMap::new()), Ok(0));
+    }
+
+    #[test]
+    fn test_errors() {
+        let duplicate = vec![
+            reaction(&[(1, "ORE")], (1, "A")),
+            reaction(&[(2, "ORE")], (1, "A")),
+        ];
+        assert_eq!(
+            Graph::new(&duplicate, &["ORE"]).unwrap_err(),
+            Error::DuplicateProducer("A".to_string())
+        );
+
+        let missing = vec![reaction(&[(1, "X")], (1, "A"))];
+        assert_eq!(
+            Graph::new(&missing, &["ORE"]).unwrap_err(),
+            Error::MissingProducer {
+                chemical: "X".to_string(),
+                consumer: "A".to_string()
+            }
+        );
+
+        let cycle = vec![
+            reaction(&[(1, "ORE"), (1, "C")], (1, "A")),
+            reaction(&[(1, "A")], (1, "B")),
+            reaction(&[(1, "B")], (1, "C")),
+        ];
+        let error = Graph::new(&cycle, &["ORE"]).unwrap_err();
+        assert_eq!(
+            error,
+            Error::Cycle(vec!["A".to_string(), "C".to_string(), "B".to_string()])
+        );
+        assert_eq!(
+            error.to_string(),
+            "Reactions form a cycle: A <- C <- B <- A"
+        );
+
+        let zero = vec![reaction(&[(1, "ORE")], (0, "A"))];
+        assert_eq!(
+            Graph::new(&zero, &["ORE"]).unwrap_err(),
+            Error::ZeroOutput("A".to_string())
+        );
+
+        let graph = example();
+        assert_eq!(
+            graph.requirements("GOLD", 1),
+            Err(Error::Unreachable("GOLD".to_string()))
+        );
+        assert_eq!(graph.requirements("FUEL", u64::MAX), Err(Error::Overflow));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::Point;
+
+/// An inclusive rectangle of points.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    /// The smallest bounds containing every point, or `None` if there are
+    /// no points.
+    pub fn of<I: IntoIterator<Item = Point>>(points: I) -> Option<Bounds> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(
+            Bounds {
+                min: first,
+                max: first,
+            },
+            |b, p| b.extend(p),
+        ))
+    }
+
+    /// The smallest bounds containing both `self` and `p`.
+    pub fn extend(self, p: Point) -> Bounds {
+        Bounds {
+            min: Point::new(self.min.x.min(p.x), self.min.y.min(p.y)),
+            max: Point::new(self.max.x.max(p.x), self.max.y.max(p.y)),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1) as usize
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x) && (self.min.y..=self.max.y).contains(&p.y)
+    }
+
+    /// Every point in the bounds, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let Bounds { min, max } = *self;
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Point::new(x, y)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bounds() {
+        assert_eq!(Bounds::of(Vec::new()), None);
+        let bounds =
+            Bounds::of(vec![Point::new(2, -1), Point::new(-1, 3), Point::new(0, 0)]).unwrap();
+        assert_eq!(bounds.min, Point::new(-1, -1));
+        assert_eq!(bounds.max, Point::new(2, 3));
+        assert_eq!((bounds.width(), bounds.height()), (4, 5));
+        assert!(bounds.contains(Point::new(2, 3)));
+        assert!(!bounds.contains(Point::new(3, 3)));
+
+        let small = Bounds::of(vec![Point::new(0, 0), Point::new(1, 1)]).unwrap();
+        let points: Vec<_> = small.points().collect();
+        assert_eq!(
+            points,
+            vec![
+                Point::new(0, 0),
+                Point::new(1, 0),
+                Point::new(0, 1),
+                Point::new(1, 1)
+            ]
+        );
+    }
+}
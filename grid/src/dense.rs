@@ -0,0 +1,276 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use crate::{Bounds, Point};
+
+/// An error parsing a character map.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseError {
+    /// A line whose length differs from the first line's.
+    RaggedLine {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+    /// A character that does not map to a cell.
+    InvalidCell { point: Point, ch: char },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::RaggedLine {
+                line,
+                width,
+                expected,
+            } => write!(
+                f,
+                "Line {} is {} characters wide, expected {}",
+                line + 1,
+                width,
+                expected
+            ),
+            ParseError::InvalidCell { point, ch } => {
+                write!(f, "Invalid cell {:?} at ({}, {})", ch, point.x, point.y)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A rectangular grid with its top left cell at the origin.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dense<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Dense<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Dense<T> {
+        Dense {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+
+impl<T> Dense<T> {
+    /// Wraps cells stored row by row. Panics unless the cells fill whole
+    /// rows of `width`.
+    pub fn from_vec(width: usize, cells: Vec<T>) -> Dense<T> {
+        assert!(
+            width > 0 && cells.len().is_multiple_of(width),
+            "{} cells do not make rows of {}",
+            cells.len(),
+            width
+        );
+        Dense {
+            width,
+            height: cells.len() / width,
+            cells,
+        }
+    }
+
+    /// Parses a character map, one row per line. Empty lines at the end are
+    /// ignored.
+    pub fn parse<F>(text: &str, mut cell: F) -> Result<Dense<T>, ParseError>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let mut lines: Vec<_> = text.lines().collect();
+        while lines.last() == Some(&"") {
+            lines.pop();
+        }
+        let width = lines.first().map_or(0, |line| line.chars().count());
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            let mut line_width = 0;
+            for (x, ch) in line.chars().enumerate() {
+                let point = Point::new(x as i64, y as i64);
+                cells.push(cell(ch).ok_or(ParseError::InvalidCell { point, ch })?);
+                line_width += 1;
+            }
+            if line_width != width {
+                return Err(ParseError::RaggedLine {
+                    line: y,
+                    width: line_width,
+                    expected: width,
+                });
+            }
+        }
+        Ok(Dense {
+            width,
+            height: lines.len(),
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The bounds of the grid, or `None` if it is empty.
+    pub fn bounds(&self) -> Option<Bounds> {
+        if self.cells.is_empty() {
+            None
+        } else {
+            Some(Bounds {
+                min: Point::ORIGIN,
+                max: Point::new(self.width as i64 - 1, self.height as i64 - 1),
+            })
+        }
+    }
+
+    fn index_of(&self, p: Point) -> Option<usize> {
+        let x = usize::try_from(p.x).ok().filter(|&x| x < self.width)?;
+        let y = usize::try_from(p.y).ok().filter(|&y| y < self.height)?;
+        Some(y * self.width + x)
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        self.index_of(p).is_some()
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.index_of(p).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.index_of(p).map(move |i| &mut self.cells[i])
+    }
+
+    /// Every point of the grid, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| Point::new((i % width) as i64, (i / width) as i64))
+    }
+
+    /// Every cell with its point, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` panics on zero, and an empty grid has no rows anyway.
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// A grid of the same size with `f` applied to each cell.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Dense<U> {
+        Dense {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// The orthogonal neighbours of `p` that are inside the grid.
+    pub fn neighbors(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        IntoIterator::into_iter(p.neighbors()).filter(move |&n| self.contains(n))
+    }
+
+    /// Draws the grid, one line per row.
+    pub fn render<F: Fn(&T) -> char>(&self, cell: F) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            text.extend(row.iter().map(&cell));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl<T> Index<Point> for Dense<T> {
+    type Output = T;
+
+    fn index(&self, p: Point) -> &T {
+        match self.get(p) {
+            Some(cell) => cell,
+            None => panic!("Point ({}, {}) is outside the grid", p.x, p.y),
+        }
+    }
+}
+
+impl<T> IndexMut<Point> for Dense<T> {
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        match self.index_of(p) {
+            Some(i) => &mut self.cells[i],
+            None => panic!("Point ({}, {}) is outside the grid", p.x, p.y),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn wall(c: char) -> Option<bool> {
+        match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = Dense::parse("#..\n.#.\n\n", wall).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert!(grid[Point::new(1, 1)]);
+        assert!(!grid[Point::new(2, 0)]);
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+        assert_eq!(grid.get(Point::new(0, -1)), None);
+        let walls: Vec<_> = grid.iter().filter(|(_, &c)| c).map(|(p, _)| p).collect();
+        assert_eq!(walls, vec![Point::new(0, 0), Point::new(1, 1)]);
+        assert_eq!(grid.render(|&c| if c { '#' } else { '.' }), "#..\n.#.\n");
+
+        assert_eq!(
+            Dense::parse("#.\n#", wall),
+            Err(ParseError::RaggedLine {
+                line: 1,
+                width: 1,
+                expected: 2
+            })
+        );
+        assert_eq!(
+            Dense::parse("#.\n#x", wall),
+            Err(ParseError::InvalidCell {
+                point: Point::new(1, 1),
+                ch: 'x'
+            })
+        );
+        let empty = Dense::parse("", wall).unwrap();
+        assert_eq!(empty.bounds(), None);
+        assert_eq!(empty.render(|_| '#'), "");
+    }
+
+    #[test]
+    fn test_cells() {
+        let mut grid = Dense::new(3, 2, 0);
+        grid[Point::new(2, 1)] = 5;
+        *grid.get_mut(Point::new(0, 0)).unwrap() = 1;
+        assert_eq!(grid.cells(), &[1, 0, 0, 0, 0, 5]);
+        assert_eq!(grid, Dense::from_vec(3, vec![1, 0, 0, 0, 0, 5]));
+        assert_eq!(grid.bounds().unwrap().max, Point::new(2, 1));
+        assert_eq!(
+            grid.map(|&c| c > 0).cells(),
+            &[true, false, false, false, false, true]
+        );
+
+        let neighbors: Vec<_> = grid.neighbors(Point::new(0, 0)).collect();
+        assert_eq!(neighbors, vec![Point::new(1, 0), Point::new(0, 1)]);
+        assert_eq!(grid.neighbors(Point::new(1, 1)).count(), 3);
+    }
+}
@@ -0,0 +1,481 @@
+//! Writing grids as images and animations.
+//!
+//! Grids are drawn from palette indices, one `u8` per cell, so each puzzle
+//! only decides what its cells mean and the palette decides how they look.
+//! PPM and PGM are always available; PNG and GIF need the `png` and `gif`
+//! features.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::Dense;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(0xff, 0xff, 0xff);
+
+    /// Perceived brightness, for grayscale output.
+    pub fn luma(self) -> u8 {
+        let Rgb(r, g, b) = self;
+        ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+    }
+}
+
+/// An error parsing a colour or a palette.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseColorError(String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid colour {:?}, expected RRGGBB", self.0)
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl FromStr for Rgb {
+    type Err = ParseColorError;
+
+    /// Parses hex `RRGGBB`, with or without a leading `#`.
+    fn from_str(s: &str) -> Result<Rgb, ParseColorError> {
+        let hex = s.trim().trim_start_matches('#');
+        let error = || ParseColorError(s.to_string());
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| error());
+        Ok(Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+/// The colours of palette indices 0, 1, 2 and so on. Indices past the end
+/// use the last colour.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Palette {
+    colors: Vec<Rgb>,
+}
+
+impl Palette {
+    /// Panics if `colors` is empty.
+    pub fn new(colors: Vec<Rgb>) -> Palette {
+        assert!(!colors.is_empty(), "A palette needs a colour");
+        Palette { colors }
+    }
+
+    pub fn color(&self, index: u8) -> Rgb {
+        let last = self.colors.len() - 1;
+        self.colors[(index as usize).min(last)]
+    }
+}
+
+impl Default for Palette {
+    /// Black, white, then grey for anything else.
+    fn default() -> Palette {
+        Palette::new(vec![Rgb::BLACK, Rgb::WHITE, Rgb(0x80, 0x80, 0x80)])
+    }
+}
+
+impl FromStr for Palette {
+    type Err = ParseColorError;
+
+    /// Parses comma separated colours, e.g. `000000,ffffff`.
+    fn from_str(s: &str) -> Result<Palette, ParseColorError> {
+        let colors = s.split(',').map(str::parse).collect::<Result<_, _>>()?;
+        Ok(Palette::new(colors))
+    }
+}
+
+/// An image file format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Ppm,
+    Pgm,
+    Png,
+    Gif,
+}
+
+impl Format {
+    /// The format for the extension of `path`.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(Format::Ppm),
+            "pgm" => Some(Format::Pgm),
+            "png" => Some(Format::Png),
+            "gif" => Some(Format::Gif),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Format::Ppm => "PPM",
+            Format::Pgm => "PGM",
+            Format::Png => "PNG",
+            Format::Gif => "GIF",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A path whose extension is not a known format.
+    UnknownFormat(PathBuf),
+    /// A format whose feature this build was compiled without.
+    Unsupported(Format),
+    /// Frames of an animation that differ in size from the first.
+    FrameSize {
+        frame: usize,
+        size: (usize, usize),
+        expected: (usize, usize),
+    },
+    NoFrames,
+    Encoding(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::UnknownFormat(path) => write!(
+                f,
+                "Cannot tell the image format of {}, expected .ppm, .pgm, .png or .gif",
+                path.display()
+            ),
+            Error::Unsupported(format) => write!(
+                f,
+                "{} output needs the {:?} feature",
+                format,
+                format.to_string().to_lowercase()
+            ),
+            Error::FrameSize {
+                frame,
+                size,
+                expected,
+            } => write!(
+                f,
+                "Frame {} is {}x{}, expected {}x{}",
+                frame, size.0, size.1, expected.0, expected.1
+            ),
+            Error::NoFrames => write!(f, "An animation needs at least one frame"),
+            Error::Encoding(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+/// An RGB raster.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    /// Draws each cell of `grid` as a `scale` by `scale` square in the
+    /// palette colour of its index.
+    pub fn new(grid: &Dense<u8>, palette: &Palette, scale: usize) -> Image {
+        let scale = scale.max(1);
+        let mut pixels = Vec::with_capacity(grid.cells().len() * scale * scale);
+        for row in grid.rows() {
+            let line: Vec<_> = row
+                .iter()
+                .flat_map(|&cell| std::iter::repeat_n(palette.color(cell), scale))
+                .collect();
+            for _ in 0..scale {
+                pixels.extend_from_slice(&line);
+            }
+        }
+        Image {
+            width: grid.width() * scale,
+            height: grid.height() * scale,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The pixels, row by row.
+    pub fn pixels(&self) -> &[Rgb] {
+        &self.pixels
+    }
+
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn rgb_bytes(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|&Rgb(r, g, b)| [r, g, b])
+            .collect()
+    }
+
+    /// Writes a binary PPM.
+    pub fn write_ppm<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&self.rgb_bytes())
+    }
+
+    /// Writes a binary PGM of the brightness of each pixel.
+    pub fn write_pgm<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(out, "P5\n{} {}\n255\n", self.width, self.height)?;
+        let gray: Vec<_> = self.pixels.iter().map(|p| p.luma()).collect();
+        out.write_all(&gray)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, out: W) -> Result<(), Error> {
+        let mut encoder = png::Encoder::new(out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let encoding = |e: png::EncodingError| Error::Encoding(e.to_string());
+        let mut writer = encoder.write_header().map_err(encoding)?;
+        writer.write_image_data(&self.rgb_bytes()).map_err(encoding)
+    }
+
+    /// Writes the image in `format`. A GIF is a single frame animation.
+    pub fn write<W: Write>(&self, out: W, format: Format) -> Result<(), Error> {
+        match format {
+            Format::Ppm => Ok(self.write_ppm(out)?),
+            Format::Pgm => Ok(self.write_pgm(out)?),
+            #[cfg(feature = "png")]
+            Format::Png => self.write_png(out),
+            Format::Gif => write_gif(std::slice::from_ref(self), out, 0),
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::Unsupported(format)),
+        }
+    }
+
+    /// Writes the image to `path`, in the format of its extension.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let format = Format::from_path(path).ok_or_else(|| Error::UnknownFormat(path.into()))?;
+        // Encode first, so a failure leaves no partial file behind.
+        let mut bytes = Vec::new();
+        self.write(&mut bytes, format)?;
+        Ok(fs::write(path, bytes)?)
+    }
+}
+
+fn check_sizes(frames: &[Image]) -> Result<(), Error> {
+    let expected = frames.first().ok_or(Error::NoFrames)?.size();
+    match frames.iter().position(|f| f.size() != expected) {
+        Some(frame) => Err(Error::FrameSize {
+            frame,
+            size: frames[frame].size(),
+            expected,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Writes a looping GIF that shows each frame for `delay` hundredths of a
+/// second.
+#[cfg(feature = "gif")]
+pub fn write_gif<W: Write>(frames: &[Image], out: W, delay: u16) -> Result<(), Error> {
+    use std::convert::TryFrom;
+
+    check_sizes(frames)?;
+    let (width, height) = frames[0].size();
+    let dimension = |n| {
+        u16::try_from(n)
+            .map_err(|_| Error::Encoding(format!("{} pixels is too large for a GIF", n)))
+    };
+    let (width, height) = (dimension(width)?, dimension(height)?);
+    let encoding = |e: gif::EncodingError| Error::Encoding(e.to_string());
+    let mut encoder = gif::Encoder::new(out, width, height, &[]).map_err(encoding)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(encoding)?;
+    for image in frames {
+        let mut frame = gif::Frame::from_rgb(width, height, &image.rgb_bytes());
+        frame.delay = delay;
+        encoder.write_frame(&frame).map_err(encoding)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "gif"))]
+pub fn write_gif<W: Write>(frames: &[Image], _out: W, _delay: u16) -> Result<(), Error> {
+    check_sizes(frames)?;
+    Err(Error::Unsupported(Format::Gif))
+}
+
+/// Saves an animation to `path`. A `.gif` path gets a looping GIF with
+/// `delay` hundredths of a second per frame; any other format gets one
+/// numbered file per frame, so `out/game.png` becomes `out/game_0000.png`,
+/// `out/game_0001.png` and so on. Returns the paths written.
+pub fn save_animation(frames: &[Image], path: &Path, delay: u16) -> Result<Vec<PathBuf>, Error> {
+    let format = Format::from_path(path).ok_or_else(|| Error::UnknownFormat(path.into()))?;
+    check_sizes(frames)?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    if format == Format::Gif {
+        let mut bytes = Vec::new();
+        write_gif(frames, &mut bytes, delay)?;
+        fs::write(path, bytes)?;
+        return Ok(vec![path.into()]);
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    let digits = (frames.len() - 1).to_string().len().max(4);
+    let mut paths = Vec::with_capacity(frames.len());
+    for (i, frame) in frames.iter().enumerate() {
+        let name = format!("{}_{:0width$}.{}", stem, i, extension, width = digits);
+        let frame_path = path.with_file_name(name);
+        frame.save(&frame_path)?;
+        paths.push(frame_path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn checkerboard() -> Dense<u8> {
+        Dense::from_vec(2, vec![0, 1, 1, 2])
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette: Palette = "#000000, ff8000".parse().unwrap();
+        assert_eq!(palette.color(0), Rgb::BLACK);
+        assert_eq!(palette.color(1), Rgb(0xff, 0x80, 0));
+        assert_eq!(palette.color(7), Rgb(0xff, 0x80, 0));
+        assert!("00000".parse::<Palette>().is_err());
+        assert!("000000,".parse::<Palette>().is_err());
+        assert!("00000g".parse::<Rgb>().is_err());
+        assert_eq!(Rgb::WHITE.luma(), 255);
+        assert_eq!(Rgb::BLACK.luma(), 0);
+    }
+
+    #[test]
+    fn test_image() {
+        let image = Image::new(&checkerboard(), &Palette::default(), 2);
+        assert_eq!((image.width(), image.height()), (4, 4));
+        let gray = Rgb(0x80, 0x80, 0x80);
+        assert_eq!(
+            &image.pixels()[8..],
+            &[
+                Rgb::WHITE,
+                Rgb::WHITE,
+                gray,
+                gray,
+                Rgb::WHITE,
+                Rgb::WHITE,
+                gray,
+                gray
+            ]
+        );
+
+        let image = Image::new(&checkerboard(), &Palette::default(), 1);
+        let mut ppm = Vec::new();
+        image.write(&mut ppm, Format::Ppm).unwrap();
+        assert_eq!(&ppm[..11], b"P6\n2 2\n255\n");
+        assert_eq!(
+            &ppm[11..],
+            &[0, 0, 0, 255, 255, 255, 255, 255, 255, 128, 128, 128]
+        );
+        let mut pgm = Vec::new();
+        image.write(&mut pgm, Format::Pgm).unwrap();
+        assert_eq!(pgm, b"P5\n2 2\n255\n\x00\xff\xff\x80");
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Format::from_path(Path::new("a/b.PNG")), Some(Format::Png));
+        assert_eq!(Format::from_path(Path::new("b.gif")), Some(Format::Gif));
+        assert_eq!(Format::from_path(Path::new("b.jpg")), None);
+        assert_eq!(Format::from_path(Path::new("b")), None);
+    }
+
+    #[test]
+    fn test_animation() {
+        let dir = std::env::temp_dir().join(format!("grid-image-{}", std::process::id()));
+        let small = Image::new(&checkerboard(), &Palette::default(), 1);
+        let large = Image::new(&checkerboard(), &Palette::default(), 2);
+        let paths = save_animation(&[small.clone(), small.clone()], &dir.join("f.ppm"), 5).unwrap();
+        assert_eq!(paths, vec![dir.join("f_0000.ppm"), dir.join("f_0001.ppm")]);
+        let mut ppm = Vec::new();
+        small.write_ppm(&mut ppm).unwrap();
+        assert_eq!(fs::read(&paths[1]).unwrap(), ppm);
+
+        match save_animation(&[small.clone(), large], &dir.join("f.ppm"), 5) {
+            Err(Error::FrameSize { frame: 1, .. }) => {}
+            other => panic!("Unexpected {:?}", other),
+        }
+        assert!(matches!(
+            save_animation(&[], &dir.join("f.ppm"), 5),
+            Err(Error::NoFrames)
+        ));
+        assert!(matches!(
+            save_animation(&[small], &dir.join("f.txt"), 5),
+            Err(Error::UnknownFormat(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_png() {
+        let image = Image::new(&checkerboard(), &Palette::default(), 3);
+        let mut png = Vec::new();
+        image.write(&mut png, Format::Png).unwrap();
+        let decoder = png::Decoder::new(std::io::Cursor::new(png));
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (6, 6));
+        assert_eq!(pixels, image.rgb_bytes());
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn test_gif() {
+        let frames = vec![
+            Image::new(&checkerboard(), &Palette::default(), 1),
+            Image::new(
+                &Dense::from_vec(2, vec![1, 1, 1, 1]),
+                &Palette::default(),
+                1,
+            ),
+        ];
+        let mut gif = Vec::new();
+        write_gif(&frames, &mut gif, 10).unwrap();
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(std::io::Cursor::new(gif)).unwrap();
+        let mut count = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!((frame.width, frame.height, frame.delay), (2, 2, 10));
+            count += 1;
+        }
+        assert_eq!(count, 2);
+    }
+}
@@ -0,0 +1,17 @@
+//! Two-dimensional grids on integer coordinates.
+//!
+//! Coordinates follow the screen convention: `x` grows to the right and `y`
+//! grows downwards, so the first line of a character map is row 0 and
+//! `Direction::Up` decreases `y`.
+
+mod bounds;
+mod dense;
+pub mod image;
+pub mod ocr;
+mod point;
+mod sparse;
+
+pub use bounds::Bounds;
+pub use dense::{Dense, ParseError};
+pub use point::{Direction, Point, Turn};
+pub use sparse::Sparse;
@@ -0,0 +1,409 @@
+//! Reading the block letters that some puzzles draw as their answer.
+//!
+//! Two fonts are known: letters 4 pixels wide and 6 high on a pitch of 5,
+//! and letters 6 wide and 10 high on a pitch of 8. The font is picked by
+//! the height of the lit pixels.
+
+use std::fmt;
+
+use crate::Dense;
+
+struct Font {
+    height: usize,
+    /// Columns from the start of one letter to the start of the next.
+    /// Letters as wide as the pitch need no blank column after them.
+    pitch: usize,
+    /// Each letter as rows of `#` and `.`.
+    glyphs: &'static [(char, &'static [&'static str])],
+}
+
+const SMALL: Font = Font {
+    height: 6,
+    pitch: 5,
+    glyphs: &[
+        ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+        ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+        ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+        ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+        ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+        ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+        ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+        ('I', &["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+        ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+        ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+        ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+        ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+        ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+        ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+        ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('Y', &["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+        ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+    ],
+};
+
+// One row per line, so the letters can be read.
+#[rustfmt::skip]
+const LARGE: Font = Font {
+    height: 10,
+    pitch: 8,
+    glyphs: &[
+        ('A', &[
+            "..##..",
+            ".#..#.",
+            "#....#",
+            "#....#",
+            "#....#",
+            "######",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#....#",
+        ]),
+        ('B', &[
+            "#####.",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#####.",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#####.",
+        ]),
+        ('C', &[
+            ".####.",
+            "#....#",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#....#",
+            ".####.",
+        ]),
+        ('E', &[
+            "######",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#####.",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "######",
+        ]),
+        ('F', &[
+            "######",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#####.",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+        ]),
+        ('G', &[
+            ".####.",
+            "#....#",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#..###",
+            "#....#",
+            "#....#",
+            "#...##",
+            ".###.#",
+        ]),
+        ('H', &[
+            "#....#",
+            "#....#",
+            "#....#",
+            "#....#",
+            "######",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#....#",
+        ]),
+        ('J', &[
+            "...###",
+            "....#.",
+            "....#.",
+            "....#.",
+            "....#.",
+            "....#.",
+            "....#.",
+            "#...#.",
+            "#...#.",
+            ".###..",
+        ]),
+        ('K', &[
+            "#....#",
+            "#...#.",
+            "#..#..",
+            "#.#...",
+            "##....",
+            "##....",
+            "#.#...",
+            "#..#..",
+            "#...#.",
+            "#....#",
+        ]),
+        ('L', &[
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "######",
+        ]),
+        ('N', &[
+            "#....#",
+            "##...#",
+            "##...#",
+            "#.#..#",
+            "#.#..#",
+            "#..#.#",
+            "#..#.#",
+            "#...##",
+            "#...##",
+            "#....#",
+        ]),
+        ('P', &[
+            "#####.",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#####.",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+        ]),
+        ('R', &[
+            "#####.",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#####.",
+            "#..#..",
+            "#...#.",
+            "#...#.",
+            "#....#",
+            "#....#",
+        ]),
+        ('X', &[
+            "#....#",
+            "#....#",
+            ".#..#.",
+            ".#..#.",
+            "..##..",
+            "..##..",
+            ".#..#.",
+            ".#..#.",
+            "#....#",
+            "#....#",
+        ]),
+        ('Z', &[
+            "######",
+            ".....#",
+            ".....#",
+            "....#.",
+            "...#..",
+            "..#...",
+            ".#....",
+            "#.....",
+            "#.....",
+            "######",
+        ]),
+    ],
+};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// No pixel is lit.
+    Blank,
+    /// Lit rows that do not match the height of a known font.
+    UnknownHeight(usize),
+    /// Lit pixels that are not a known letter, drawn with `#` and `.`.
+    UnknownGlyph { column: usize, bitmap: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Blank => write!(f, "There are no letters to read"),
+            Error::UnknownHeight(height) => write!(
+                f,
+                "Letters are {} pixels high, expected {} or {}",
+                height, SMALL.height, LARGE.height
+            ),
+            Error::UnknownGlyph { column, bitmap } => {
+                write!(f, "Unknown letter at column {}:\n{}", column, bitmap)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Reads the letters drawn by the `true` cells of `grid`. Blank rows and
+/// columns around and between the letters are skipped.
+pub fn read(grid: &Dense<bool>) -> Result<String, Error> {
+    let rows: Vec<&[bool]> = grid.rows().collect();
+    let lit = |row: &&[bool]| row.iter().any(|&pixel| pixel);
+    let top = rows.iter().position(lit).ok_or(Error::Blank)?;
+    let bottom = rows.iter().rposition(lit).unwrap();
+    let rows = &rows[top..=bottom];
+    let font = [SMALL, LARGE]
+        .iter()
+        .find(|font| font.height == rows.len())
+        .ok_or(Error::UnknownHeight(rows.len()))?;
+    let pitch = font.pitch;
+
+    let width = grid.width();
+    let blank = |x: usize| x >= width || rows.iter().all(|row| !row[x]);
+    let matches = |x: usize, glyph: &[&str]| {
+        let glyph_width = glyph[0].len();
+        x + glyph_width <= width
+            && (glyph_width == pitch || blank(x + glyph_width))
+            && rows.iter().zip(glyph).all(|(row, pixels)| {
+                pixels
+                    .chars()
+                    .zip(&row[x..])
+                    .all(|(c, &pixel)| (c == '#') == pixel)
+            })
+    };
+
+    let mut text = String::new();
+    let mut x = 0;
+    while x < width {
+        if blank(x) {
+            x += 1;
+            continue;
+        }
+        match font.glyphs.iter().find(|(_, glyph)| matches(x, glyph)) {
+            Some((letter, glyph)) => {
+                text.push(*letter);
+                x += glyph[0].len();
+            }
+            None => {
+                let end = (x..(x + pitch).min(width))
+                    .find(|&x| blank(x))
+                    .unwrap_or_else(|| (x + pitch).min(width));
+                let bitmap = rows
+                    .iter()
+                    .map(|row| {
+                        row[x..end]
+                            .iter()
+                            .map(|&pixel| if pixel { '#' } else { '.' })
+                            .collect::<String>()
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                return Err(Error::UnknownGlyph { column: x, bitmap });
+            }
+        }
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn grid(text: &str) -> Dense<bool> {
+        Dense::parse(text, |c| Some(c == '#')).unwrap()
+    }
+
+    #[test]
+    fn test_small() {
+        let text = "\
+.....................
+.###..####.#..#.#...#
+.#..#....#.#.#..#...#
+.#..#...#..##....#.#.
+.###...#...#.#....#..
+.#....#....#.#....#..
+.#....####.#..#...#..
+.....................
+";
+        assert_eq!(read(&grid(text)), Ok("PZKY".to_string()));
+
+        // Y fills its pitch, so the next letter may follow without a gap.
+        let text = "\
+#...#.##.
+#...##..#
+.#.#.#..#
+..#..####
+..#..#..#
+..#..#..#
+";
+        assert_eq!(read(&grid(text)), Ok("YA".to_string()));
+    }
+
+    #[test]
+    fn test_fonts_are_distinct() {
+        for font in &[SMALL, LARGE] {
+            for (letter, glyph) in font.glyphs {
+                let text: String = glyph.iter().map(|row| format!("{}\n", row)).collect();
+                assert_eq!(read(&grid(&text)), Ok(letter.to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_large() {
+        let letters: Vec<_> = LARGE
+            .glyphs
+            .iter()
+            .filter(|(c, _)| "NXB".contains(*c))
+            .collect();
+        let mut text = String::new();
+        for row in 0..10 {
+            for (_, glyph) in &letters {
+                text.push_str(glyph[row]);
+                text.push_str("..");
+            }
+            text.push('\n');
+        }
+        assert_eq!(read(&grid(&text)), Ok("BNX".to_string()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(read(&grid("....\n....\n")), Err(Error::Blank));
+        assert_eq!(read(&grid("#\n#\n#\n")), Err(Error::UnknownHeight(3)));
+        let text = "\
+####..##.
+#..#.#..#
+#..#.#..#
+#..#.####
+#..#.#..#
+####.#..#
+";
+        let error = read(&grid(text)).unwrap_err();
+        assert_eq!(
+            error,
+            Error::UnknownGlyph {
+                column: 0,
+                bitmap: "####\n#..#\n#..#\n#..#\n#..#\n####".to_string()
+            }
+        );
+        assert!(error
+            .to_string()
+            .starts_with("Unknown letter at column 0:\n####\n"));
+    }
+}
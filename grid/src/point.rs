@@ -0,0 +1,174 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point { x: 0, y: 0 };
+
+    pub const fn new(x: i64, y: i64) -> Point {
+        Point { x, y }
+    }
+
+    /// Manhattan distance to `other`.
+    pub fn manhattan(self, other: Point) -> u64 {
+        (self.x - other.x).unsigned_abs() + (self.y - other.y).unsigned_abs()
+    }
+
+    /// The four orthogonal neighbours, in the order of `Direction::ALL`.
+    pub fn neighbors(self) -> [Point; 4] {
+        Direction::ALL.map(|d| self + d.delta())
+    }
+
+    /// The eight orthogonal and diagonal neighbours, clockwise from the
+    /// one above.
+    pub fn neighbors8(self) -> [Point; 8] {
+        [
+            (0, -1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-1, -1),
+        ]
+        .map(|(dx, dy)| self + Point::new(dx, dy))
+    }
+}
+
+impl From<(i64, i64)> for Point {
+    fn from((x, y): (i64, i64)) -> Point {
+        Point { x, y }
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, other: Point) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i64> for Point {
+    type Output = Point;
+
+    fn mul(self, n: i64) -> Point {
+        Point::new(self.x * n, self.y * n)
+    }
+}
+
+/// A turn relative to the current heading.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Turn {
+    Left,
+    Right,
+    Around,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    /// Every direction, clockwise from `Up`.
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// The step one cell in this direction.
+    pub fn delta(self) -> Point {
+        match self {
+            Direction::Up => Point::new(0, -1),
+            Direction::Right => Point::new(1, 0),
+            Direction::Down => Point::new(0, 1),
+            Direction::Left => Point::new(-1, 0),
+        }
+    }
+
+    pub fn rotate(self, turn: Turn) -> Direction {
+        let quarters = match turn {
+            Turn::Right => 1,
+            Turn::Around => 2,
+            Turn::Left => 3,
+        };
+        Direction::ALL[(self as usize + quarters) % 4]
+    }
+
+    pub fn move_one(self, point: Point) -> Point {
+        point + self.delta()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_point() {
+        let p = Point::new(3, -4);
+        assert_eq!(p.manhattan(Point::ORIGIN), 7);
+        assert_eq!(p + Point::new(1, 1), Point::new(4, -3));
+        assert_eq!(p - p, Point::ORIGIN);
+        assert_eq!(-p * 2, Point::new(-6, 8));
+        assert_eq!(Point::from((1, 2)), Point::new(1, 2));
+        assert_eq!(
+            Point::ORIGIN.neighbors(),
+            [
+                Point::new(0, -1),
+                Point::new(1, 0),
+                Point::new(0, 1),
+                Point::new(-1, 0)
+            ]
+        );
+        let neighbors = Point::new(5, 5).neighbors8();
+        assert!(neighbors.iter().all(|n| n.manhattan(Point::new(5, 5)) <= 2));
+        assert_eq!(neighbors.len(), 8);
+    }
+
+    #[test]
+    fn test_direction() {
+        assert_eq!(Direction::Up.rotate(Turn::Left), Direction::Left);
+        assert_eq!(Direction::Up.rotate(Turn::Right), Direction::Right);
+        assert_eq!(Direction::Left.rotate(Turn::Right), Direction::Up);
+        assert_eq!(Direction::Down.rotate(Turn::Around), Direction::Up);
+        for &d in Direction::ALL.iter() {
+            assert_eq!(d.rotate(Turn::Left).rotate(Turn::Right), d);
+            assert_eq!(d.move_one(Point::ORIGIN), d.delta());
+        }
+        assert_eq!(Direction::Up.move_one(Point::new(2, 2)), Point::new(2, 1));
+    }
+}
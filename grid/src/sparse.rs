@@ -0,0 +1,181 @@
+use std::collections::hash_map::{self, HashMap};
+use std::iter::FromIterator;
+
+use crate::{Bounds, Dense, Point};
+
+/// A grid that stores only the cells that were set, over unbounded
+/// coordinates.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sparse<T> {
+    cells: HashMap<Point, T>,
+}
+
+impl<T> Default for Sparse<T> {
+    fn default() -> Sparse<T> {
+        Sparse {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Sparse<T> {
+    pub fn new() -> Sparse<T> {
+        Sparse::default()
+    }
+
+    /// Parses a character map, keeping the cells for which `cell` returns
+    /// a value.
+    pub fn parse<F>(text: &str, mut cell: F) -> Sparse<T>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let mut grid = Sparse::new();
+        for (y, line) in text.lines().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                if let Some(value) = cell(ch) {
+                    grid.insert(Point::new(x as i64, y as i64), value);
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.cells.get(&p)
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.cells.get_mut(&p)
+    }
+
+    /// Sets a cell and returns its previous value.
+    pub fn insert(&mut self, p: Point, value: T) -> Option<T> {
+        self.cells.insert(p, value)
+    }
+
+    pub fn remove(&mut self, p: Point) -> Option<T> {
+        self.cells.remove(&p)
+    }
+
+    /// The set cells, in no particular order.
+    pub fn iter(&self) -> hash_map::Iter<'_, Point, T> {
+        self.cells.iter()
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.cells.keys().copied()
+    }
+
+    /// Finds a point whose cell satisfies `predicate`.
+    pub fn find<F: Fn(&T) -> bool>(&self, predicate: F) -> Option<Point> {
+        self.cells
+            .iter()
+            .find(|(_, value)| predicate(value))
+            .map(|(&p, _)| p)
+    }
+
+    /// The smallest bounds containing every set cell, or `None` if no cell
+    /// is set.
+    pub fn bounds(&self) -> Option<Bounds> {
+        Bounds::of(self.points())
+    }
+
+    /// Draws the cells within `bounds`, one line per row. `cell` gets
+    /// `None` for cells that are not set.
+    pub fn render_in<F: Fn(Option<&T>) -> char>(&self, bounds: Bounds, cell: F) -> String {
+        let mut text = String::with_capacity((bounds.width() + 1) * bounds.height());
+        for y in bounds.min.y..=bounds.max.y {
+            for x in bounds.min.x..=bounds.max.x {
+                text.push(cell(self.get(Point::new(x, y))));
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Copies the cells within `bounds` into a dense grid whose origin is
+    /// `bounds.min`. `cell` gets `None` for cells that are not set.
+    pub fn to_dense<U, F: Fn(Option<&T>) -> U>(&self, bounds: Bounds, cell: F) -> Dense<U> {
+        let cells = bounds.points().map(|p| cell(self.get(p))).collect();
+        Dense::from_vec(bounds.width(), cells)
+    }
+
+    /// Draws the bounding box of the set cells.
+    pub fn render<F: Fn(Option<&T>) -> char>(&self, cell: F) -> String {
+        match self.bounds() {
+            Some(bounds) => self.render_in(bounds, cell),
+            None => String::new(),
+        }
+    }
+}
+
+impl<T> FromIterator<(Point, T)> for Sparse<T> {
+    fn from_iter<I: IntoIterator<Item = (Point, T)>>(iter: I) -> Sparse<T> {
+        Sparse {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Extend<(Point, T)> for Sparse<T> {
+    fn extend<I: IntoIterator<Item = (Point, T)>>(&mut self, iter: I) {
+        self.cells.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sparse() {
+        let mut grid: Sparse<u32> = Sparse::new();
+        assert_eq!(grid.bounds(), None);
+        assert_eq!(grid.render(|_| '#'), "");
+
+        grid.insert(Point::new(-1, 0), 1);
+        grid.insert(Point::new(1, 1), 2);
+        assert_eq!(grid.insert(Point::new(1, 1), 3), Some(2));
+        assert_eq!(grid.len(), 2);
+        assert_eq!(grid.find(|&v| v == 3), Some(Point::new(1, 1)));
+        assert_eq!(grid.find(|&v| v == 2), None);
+
+        let bounds = grid.bounds().unwrap();
+        assert_eq!(bounds.min, Point::new(-1, 0));
+        assert_eq!(bounds.max, Point::new(1, 1));
+        fn render(cell: Option<&u32>) -> char {
+            cell.map_or('.', |&v| std::char::from_digit(v, 10).unwrap())
+        }
+        assert_eq!(grid.render(render), "1..\n..3\n");
+        let wider = Bounds {
+            min: Point::new(-2, 0),
+            max: Point::new(1, 0),
+        };
+        assert_eq!(grid.render_in(wider, render), ".1..\n");
+
+        let dense = grid.to_dense(wider, |v| v.copied().unwrap_or(0));
+        assert_eq!(dense.cells(), &[0, 1, 0, 0]);
+
+        assert_eq!(grid.remove(Point::new(-1, 0)), Some(1));
+        assert_eq!(grid.render(render), "3\n");
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = Sparse::parse(".#.\n..#\n", |c| if c == '#' { Some(()) } else { None });
+        let mut points: Vec<_> = grid.points().collect();
+        points.sort_by_key(|p| (p.y, p.x));
+        assert_eq!(points, vec![Point::new(1, 0), Point::new(2, 1)]);
+
+        let collected: Sparse<_> = vec![(Point::ORIGIN, 'a')].into_iter().collect();
+        assert_eq!(collected.get(Point::ORIGIN), Some(&'a'));
+    }
+}
@@ -0,0 +1,238 @@
+//! Compares instruction decoding and whole-program runs with the legacy
+//! string-based decoder, and with the arithmetic decoder with and without
+//! the decoded-instruction cache.
+//!
+//! The programs are the puzzle inputs of the Intcode days, so the numbers
+//! reflect the workloads the interpreter actually runs.
+
+use std::convert::TryFrom;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use intcode::{Error, Instruction, Operator, ParamMode, Program, RunState, Word};
+
+const DAY_02: &str = include_str!("../../day_02/src/input");
+const DAY_05: &str = include_str!("../../day_05/src/input");
+const DAY_07: &str = include_str!("../../day_07/src/input");
+const DAY_09: &str = include_str!("../../day_09/src/input");
+
+fn parse(input: &str) -> Vec<Word> {
+    input
+        .trim()
+        .split(',')
+        .map(|s| s.parse().unwrap())
+        .collect()
+}
+
+/// The string-based decoder the interpreter used before decoding
+/// arithmetically.
+fn legacy_decode(word: Word) -> Result<Instruction, Error> {
+    let op = Operator::try_from(word % 100)?;
+    let v: Vec<_> = format!("{:05}", word).chars().collect();
+    Ok(Instruction {
+        op,
+        m1: ParamMode::try_from(v[2])?,
+        m2: ParamMode::try_from(v[1])?,
+        m3: ParamMode::try_from(v[0])?,
+    })
+}
+
+/// The address of parameter `i` of the instruction at `pc`, growing the
+/// memory to cover it.
+fn legacy_addr(mem: &mut Vec<Word>, pc: usize, rel_base: Word, mode: ParamMode, i: usize) -> usize {
+    let addr = match mode {
+        ParamMode::Position => mem[pc + i],
+        ParamMode::Relative => rel_base + mem[pc + i],
+        ParamMode::Immediate => return pc + i,
+    };
+    let addr = usize::try_from(addr).unwrap();
+    if addr >= mem.len() {
+        mem.resize(addr + 1, 0);
+    }
+    addr
+}
+
+/// Runs `code` the way the interpreter did before decoding arithmetically,
+/// with every executed instruction decoded by `legacy_decode`. Returns
+/// the same as `run`.
+fn legacy_run(code: &[Word], input: &[Word]) -> (Word, Word) {
+    use Operator::*;
+
+    let mut mem = code.to_vec();
+    let mut input = input.iter().copied();
+    let (mut pc, mut rel_base, mut last) = (0, 0, 0);
+    loop {
+        let inst = legacy_decode(mem[pc]).unwrap();
+        let params = match inst.op {
+            Add | Mul | LessThan | Equals => 3,
+            JumpIfTrue | JumpIfFalse => 2,
+            In | Out | AdjRelBase => 1,
+            Break => return (last, mem[0]),
+        };
+        let mut a = [0; 3];
+        let modes = [inst.m1, inst.m2, inst.m3];
+        for (i, (addr, &mode)) in a.iter_mut().zip(modes.iter()).take(params).enumerate() {
+            *addr = legacy_addr(&mut mem, pc, rel_base, mode, i + 1);
+        }
+        pc += params + 1;
+        match inst.op {
+            Add => mem[a[2]] = mem[a[0]] + mem[a[1]],
+            Mul => mem[a[2]] = mem[a[0]] * mem[a[1]],
+            In => mem[a[0]] = input.next().expect("Program needs more input"),
+            Out => last = mem[a[0]],
+            JumpIfTrue if mem[a[0]] != 0 => pc = usize::try_from(mem[a[1]]).unwrap(),
+            JumpIfFalse if mem[a[0]] == 0 => pc = usize::try_from(mem[a[1]]).unwrap(),
+            LessThan => mem[a[2]] = Word::from(mem[a[0]] < mem[a[1]]),
+            Equals => mem[a[2]] = Word::from(mem[a[0]] == mem[a[1]]),
+            AdjRelBase => rel_base += mem[a[0]],
+            _ => (),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Decoder {
+    Legacy,
+    Uncached,
+    Cached,
+}
+
+impl Decoder {
+    fn name(self) -> &'static str {
+        match self {
+            Decoder::Legacy => "legacy",
+            Decoder::Uncached => "uncached",
+            Decoder::Cached => "cached",
+        }
+    }
+}
+
+/// Runs `code` to completion and returns its last output and the final
+/// value of cell 0.
+fn run(code: &[Word], input: &[Word], decoder: Decoder) -> (Word, Word) {
+    let cached = match decoder {
+        Decoder::Legacy => return legacy_run(code, input),
+        Decoder::Uncached => false,
+        Decoder::Cached => true,
+    };
+    let mut prog = Program::new(code);
+    prog.set_decode_cache(cached);
+    let mut input = input.iter().copied();
+    let mut last = 0;
+    loop {
+        match prog.run_partial(&mut || input.next()).unwrap() {
+            RunState::Output(value) => last = value,
+            RunState::NeedsInput => panic!("Program needs more input"),
+            RunState::Halted => return (last, prog.peek(0)),
+        }
+    }
+}
+
+/// Day 2, part 2: search for the noun and verb that produce 19690720.
+fn noun_verb_search(code: &[Word], decoder: Decoder) -> Word {
+    for noun in 0..=99 {
+        for verb in 0..=99 {
+            let mut code = code.to_vec();
+            code[1] = noun;
+            code[2] = verb;
+            if run(&code, &[], decoder).1 == 19_690_720 {
+                return 100 * noun + verb;
+            }
+        }
+    }
+    panic!("No solution");
+}
+
+fn permutations(items: &[Word]) -> Vec<Vec<Word>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+    let mut output = Vec::new();
+    for (i, &first) in items.iter().enumerate() {
+        let mut rest = items.to_vec();
+        rest.remove(i);
+        for mut perm in permutations(&rest) {
+            perm.insert(0, first);
+            output.push(perm);
+        }
+    }
+    output
+}
+
+/// Day 7, part 1: the highest signal over all phase settings.
+fn amplifier_search(code: &[Word], decoder: Decoder) -> Word {
+    permutations(&[0, 1, 2, 3, 4])
+        .iter()
+        .map(|phases| {
+            phases
+                .iter()
+                .fold(0, |signal, &phase| run(code, &[phase, signal], decoder).0)
+        })
+        .max()
+        .unwrap()
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let words = parse(DAY_09);
+    let mut group = c.benchmark_group("decode");
+    group.bench_function("legacy", |b| {
+        b.iter(|| {
+            for &word in words.iter() {
+                let _ = black_box(legacy_decode(black_box(word)));
+            }
+        })
+    });
+    group.bench_function("arithmetic", |b| {
+        b.iter(|| {
+            for &word in words.iter() {
+                let _ = black_box(Instruction::try_from(black_box(word)));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_programs(c: &mut Criterion) {
+    let day_02 = parse(DAY_02);
+    let day_05 = parse(DAY_05);
+    let day_07 = parse(DAY_07);
+    let day_09 = parse(DAY_09);
+
+    // The legacy interpreter only makes a fair comparison if it computes
+    // the same answers.
+    assert_eq!(
+        noun_verb_search(&day_02, Decoder::Legacy),
+        noun_verb_search(&day_02, Decoder::Cached)
+    );
+    assert_eq!(
+        amplifier_search(&day_07, Decoder::Legacy),
+        amplifier_search(&day_07, Decoder::Cached)
+    );
+    for (code, input) in [(&day_05, 5), (&day_09, 2)].iter() {
+        assert_eq!(
+            run(code, &[*input], Decoder::Legacy),
+            run(code, &[*input], Decoder::Cached)
+        );
+    }
+
+    let mut group = c.benchmark_group("run");
+    group.sample_size(10);
+    for &decoder in [Decoder::Legacy, Decoder::Uncached, Decoder::Cached].iter() {
+        let name = decoder.name();
+        group.bench_with_input(BenchmarkId::new("day_02", name), &decoder, |b, &decoder| {
+            b.iter(|| noun_verb_search(&day_02, decoder))
+        });
+        group.bench_with_input(BenchmarkId::new("day_05", name), &decoder, |b, &decoder| {
+            b.iter(|| run(&day_05, &[5], decoder))
+        });
+        group.bench_with_input(BenchmarkId::new("day_07", name), &decoder, |b, &decoder| {
+            b.iter(|| amplifier_search(&day_07, decoder))
+        });
+        group.bench_with_input(BenchmarkId::new("day_09", name), &decoder, |b, &decoder| {
+            b.iter(|| run(&day_09, &[2], decoder))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode, bench_programs);
+criterion_main!(benches);
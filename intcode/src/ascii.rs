@@ -0,0 +1,291 @@
+//! Text input and output for programs that speak ASCII.
+//!
+//! Input text is fed to the program one byte per `In` instruction. Output
+//! values in the ASCII range are collected into text; anything else, like a
+//! puzzle answer printed at the end of a transcript, is kept as a separate
+//! numeric event instead of being mangled into a character.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::memory::{Dense, Memory};
+use crate::{Program, RunState, Word};
+
+#[derive(Debug)]
+pub enum Error {
+    Program(crate::Error),
+    /// Input contained a character outside the ASCII range.
+    NonAscii(char),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Program(e) => write!(f, "{}", e),
+            Error::NonAscii(c) => write!(f, "Non-ASCII input character: {:?}", c),
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<crate::Error> for Error {
+    fn from(e: crate::Error) -> Error {
+        Error::Program(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+/// A piece of program output.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Output {
+    /// A run of consecutive output values in the ASCII range.
+    Text(String),
+    /// An output value outside the ASCII range.
+    Value(Word),
+}
+
+/// Output collected by `Ascii::run`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Transcript {
+    pub output: Vec<Output>,
+    /// `RunState::NeedsInput` or `RunState::Halted`.
+    pub state: RunState,
+}
+
+impl Transcript {
+    /// All text output, skipping non-ASCII values.
+    pub fn text(&self) -> String {
+        self.output
+            .iter()
+            .filter_map(|output| match output {
+                Output::Text(text) => Some(text.as_str()),
+                Output::Value(_) => None,
+            })
+            .collect()
+    }
+
+    /// All non-ASCII output values.
+    pub fn values(&self) -> Vec<Word> {
+        self.output
+            .iter()
+            .filter_map(|output| match output {
+                Output::Value(value) => Some(*value),
+                Output::Text(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Returns the character for an output value in the ASCII range.
+pub fn to_char(value: Word) -> Option<char> {
+    if (0..128).contains(&value) {
+        Some(value as u8 as char)
+    } else {
+        None
+    }
+}
+
+/// Converts text to input values.
+pub fn encode(text: &str) -> Result<Vec<Word>, Error> {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii() {
+                Ok(Word::from(c as u8))
+            } else {
+                Err(Error::NonAscii(c))
+            }
+        })
+        .collect()
+}
+
+/// A program with a queue of ASCII input.
+#[derive(Debug)]
+pub struct Ascii<M = Dense> {
+    program: Program<M>,
+    input: VecDeque<Word>,
+}
+
+impl<M: Memory> Ascii<M> {
+    pub fn new(program: Program<M>) -> Ascii<M> {
+        Ascii {
+            program,
+            input: VecDeque::new(),
+        }
+    }
+
+    pub fn program(&self) -> &Program<M> {
+        &self.program
+    }
+
+    pub fn into_program(self) -> Program<M> {
+        self.program
+    }
+
+    /// Queues text as input.
+    pub fn push_str(&mut self, text: &str) -> Result<(), Error> {
+        self.input.extend(encode(text)?);
+        Ok(())
+    }
+
+    /// Queues a line of input, adding the newline if it is missing.
+    pub fn push_line(&mut self, line: &str) -> Result<(), Error> {
+        self.push_str(line)?;
+        if !line.ends_with('\n') {
+            self.input.push_back(Word::from(b'\n'));
+        }
+        Ok(())
+    }
+
+    /// Queues everything `reader` produces as input.
+    pub fn read_from(&mut self, reader: &mut dyn BufRead) -> Result<(), Error> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        self.push_str(&text)
+    }
+
+    /// Runs the program until it halts or needs more input than is queued.
+    pub fn run(&mut self) -> Result<Transcript, Error> {
+        let mut output: Vec<Output> = Vec::new();
+        let input = &mut self.input;
+        loop {
+            match self.program.run_partial(&mut || input.pop_front())? {
+                RunState::Output(value) => match (to_char(value), output.last_mut()) {
+                    (Some(c), Some(Output::Text(text))) => text.push(c),
+                    (Some(c), _) => output.push(Output::Text(c.to_string())),
+                    (None, _) => output.push(Output::Value(value)),
+                },
+                state => return Ok(Transcript { output, state }),
+            }
+        }
+    }
+}
+
+/// Connects a program to a line-oriented terminal. Whenever the program
+/// needs input, a line is read from `input`. Text output is written as is;
+/// non-ASCII values are written in decimal on a line of their own.
+///
+/// Returns `RunState::Halted` when the program halts, or
+/// `RunState::NeedsInput` if `input` ends while the program is waiting.
+pub fn interact<M: Memory>(
+    program: Program<M>,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> Result<RunState, Error> {
+    let mut ascii = Ascii::new(program);
+    let mut line_start = true;
+    loop {
+        let transcript = ascii.run()?;
+        for piece in transcript.output.iter() {
+            match piece {
+                Output::Text(text) => {
+                    write!(output, "{}", text)?;
+                    line_start = text.ends_with('\n');
+                }
+                Output::Value(value) => {
+                    if !line_start {
+                        writeln!(output)?;
+                    }
+                    writeln!(output, "{}", value)?;
+                    line_start = true;
+                }
+            }
+        }
+        output.flush()?;
+        if transcript.state == RunState::Halted {
+            return Ok(RunState::Halted);
+        }
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(RunState::NeedsInput);
+        }
+        ascii.push_line(&line)?;
+    }
+}
+
+/// Runs `interact` on the standard input and output.
+pub fn interact_stdio<M: Memory>(program: Program<M>) -> Result<RunState, Error> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    interact(program, &mut stdin.lock(), &mut stdout.lock())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::asm::assemble;
+
+    // Echoes a line of input, then outputs 1000 and a newline.
+    const ECHO: &str = "
+        loop:   in [c]
+                eq [c], #10, [t]
+                jt [t], #done
+                out [c]
+                jt #1, #loop
+        done:   out #1000
+                out #10
+                hlt
+        c:      .data 0
+        t:      .data 0
+    ";
+
+    fn echo() -> Program {
+        Program::new(&assemble(ECHO).unwrap())
+    }
+
+    #[test]
+    fn test_run() {
+        let mut ascii = Ascii::new(echo());
+        ascii.push_str("hi").unwrap();
+        let transcript = ascii.run().unwrap();
+        assert_eq!(transcript.state, RunState::NeedsInput);
+        assert_eq!(transcript.output, vec![Output::Text("hi".to_string())]);
+
+        ascii.push_line("!").unwrap();
+        let transcript = ascii.run().unwrap();
+        assert_eq!(transcript.state, RunState::Halted);
+        assert_eq!(
+            transcript.output,
+            vec![
+                Output::Text("!".to_string()),
+                Output::Value(1000),
+                Output::Text("\n".to_string())
+            ]
+        );
+        assert_eq!(transcript.text(), "!\n");
+        assert_eq!(transcript.values(), vec![1000]);
+    }
+
+    #[test]
+    fn test_input() {
+        assert_eq!(encode("A\n").unwrap(), vec![65, 10]);
+        assert!(matches!(encode("é"), Err(Error::NonAscii('é'))));
+
+        let mut ascii = Ascii::new(echo());
+        ascii.read_from(&mut "abc\n".as_bytes()).unwrap();
+        assert_eq!(ascii.run().unwrap().text(), "abc\n");
+        assert!(ascii.program().is_done());
+    }
+
+    #[test]
+    fn test_interact() {
+        let mut output = Vec::new();
+        let state = interact(echo(), &mut "hello\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(state, RunState::Halted);
+        assert_eq!(String::from_utf8(output).unwrap(), "hello\n1000\n\n");
+
+        let mut output = Vec::new();
+        let state = interact(echo(), &mut "".as_bytes(), &mut output).unwrap();
+        assert_eq!(state, RunState::NeedsInput);
+        assert!(output.is_empty());
+    }
+}
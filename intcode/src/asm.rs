@@ -0,0 +1,750 @@
+//! Assembler for a small Intcode assembly language.
+//!
+//! The syntax matches the output of `disasm`:
+//!
+//! ```text
+//! ; count down from 5
+//!         .equ START, 5
+//!         add #START, #0, [counter]
+//! loop:   out [counter]
+//!         add [counter], #-1, [counter]
+//!         jt [counter], #loop
+//!         hlt
+//! counter: .data 0
+//! ```
+//!
+//! Operands are `[expr]` (position mode), `#expr` (immediate mode) or
+//! `rb+expr`/`rb-expr` (relative mode). Expressions are built from integer
+//! literals (decimal or `0x` hexadecimal), labels, constants, `+`, `-`, `*`
+//! and parentheses. Labels may be referenced before they are defined.
+//!
+//! Directives:
+//!
+//! - `.data expr, ...` emits the given values,
+//! - `.fill count, expr` emits `count` copies of a value,
+//! - `.equ name, expr` defines a constant.
+//!
+//! The `count` of `.fill` and the value of `.equ` can only refer to symbols
+//! defined on earlier lines. A `.fill` may not take the program past 2^24
+//! cells.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::{Instruction, Operator, ParamMode, Word};
+
+/// The most cells a program may have. Only a `.fill` gets near it, and
+/// then by mistake.
+const MAX_LEN: usize = 1 << 24;
+
+const OPERATORS: [Operator; 10] = [
+    Operator::Add,
+    Operator::Mul,
+    Operator::In,
+    Operator::Out,
+    Operator::JumpIfTrue,
+    Operator::JumpIfFalse,
+    Operator::LessThan,
+    Operator::Equals,
+    Operator::AdjRelBase,
+    Operator::Break,
+];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    UnexpectedChar(char),
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    InvalidNumber(String),
+    UnknownMnemonic(String),
+    UnknownDirective(String),
+    OperandCount {
+        expected: usize,
+        found: usize,
+    },
+    ImmediateDestination,
+    UndefinedSymbol(String),
+    DuplicateSymbol(String),
+    NegativeFill(Word),
+    /// A `.fill` that takes the program past `MAX_LEN` cells.
+    TooLarge,
+    Overflow,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ErrorKind::*;
+
+        match self {
+            UnexpectedChar(c) => write!(f, "Unexpected character: {:?}", c),
+            UnexpectedToken(t) => write!(f, "Unexpected token: {}", t),
+            UnexpectedEnd => write!(f, "Unexpected end of line"),
+            InvalidNumber(n) => write!(f, "Invalid number: {}", n),
+            UnknownMnemonic(m) => write!(f, "Unknown mnemonic: {}", m),
+            UnknownDirective(d) => write!(f, "Unknown directive: {}", d),
+            OperandCount { expected, found } => {
+                write!(f, "Expected {} operands, found {}", expected, found)
+            }
+            ImmediateDestination => write!(f, "Destination operand cannot be immediate"),
+            UndefinedSymbol(s) => write!(f, "Undefined symbol: {}", s),
+            DuplicateSymbol(s) => write!(f, "Duplicate symbol: {}", s),
+            NegativeFill(n) => write!(f, "Negative fill count: {}", n),
+            TooLarge => write!(f, "Program is longer than {} cells", MAX_LEN),
+            Overflow => write!(f, "Value does not fit in a memory cell"),
+        }
+    }
+}
+
+/// An assembly error. `line` and `column` are 1-based.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Error {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Ident(String),
+    Num(i128),
+    Punct(char),
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Ident(s) => write!(f, "{}", s),
+            Tok::Num(n) => write!(f, "{}", n),
+            Tok::Punct(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '.'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+/// Splits a line into tokens paired with their 1-based column.
+fn tokenize(line: &str, line_no: usize) -> Result<Vec<(Tok, usize)>, Error> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c == ';' {
+            break;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = if let Some(hex) = text.strip_prefix("0x") {
+                i128::from_str_radix(hex, 16)
+            } else {
+                text.parse()
+            };
+            match value {
+                Ok(value) => tokens.push((Tok::Num(value), column)),
+                Err(_) => {
+                    return Err(Error {
+                        line: line_no,
+                        column,
+                        kind: ErrorKind::InvalidNumber(text),
+                    })
+                }
+            }
+        } else if is_ident_start(c) {
+            let start = i;
+            while i < chars.len() && is_ident_char(chars[i]) {
+                i += 1;
+            }
+            tokens.push((Tok::Ident(chars[start..i].iter().collect()), column));
+        } else if ",:[]#+-*()".contains(c) {
+            tokens.push((Tok::Punct(c), column));
+            i += 1;
+        } else {
+            return Err(Error {
+                line: line_no,
+                column,
+                kind: ErrorKind::UnexpectedChar(c),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Num(i128),
+    Sym(String, usize),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug)]
+struct Operand {
+    mode: ParamMode,
+    expr: Expr,
+    column: usize,
+}
+
+#[derive(Debug)]
+enum Stmt {
+    Instruction(Operator, Vec<Operand>),
+    Data(Vec<(Expr, usize)>),
+    Fill(usize, Expr, usize),
+}
+
+struct Parser<'a> {
+    tokens: &'a [(Tok, usize)],
+    pos: usize,
+    line: usize,
+    line_len: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Tok> {
+        self.tokens.get(self.pos).map(|(tok, _)| tok)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&'a Tok> {
+        self.tokens.get(self.pos + offset).map(|(tok, _)| tok)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map(|&(_, column)| column)
+            .unwrap_or(self.line_len + 1)
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error {
+            line: self.line,
+            column: self.column(),
+            kind,
+        }
+    }
+
+    fn unexpected(&self) -> Error {
+        match self.peek() {
+            Some(tok) => self.error(ErrorKind::UnexpectedToken(tok.to_string())),
+            None => self.error(ErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Tok::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn ident(&mut self) -> Result<&'a str, Error> {
+        match self.peek() {
+            Some(Tok::Ident(name)) => {
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Parses an expression along with the column it starts at.
+    fn located_expr(&mut self) -> Result<(Expr, usize), Error> {
+        let column = self.column();
+        Ok((self.expr()?, column))
+    }
+
+    fn expr(&mut self) -> Result<Expr, Error> {
+        let mut lhs = self.term()?;
+        loop {
+            if self.eat('+') {
+                lhs = Expr::Add(Box::new(lhs), Box::new(self.term()?));
+            } else if self.eat('-') {
+                lhs = Expr::Sub(Box::new(lhs), Box::new(self.term()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, Error> {
+        let mut lhs = self.factor()?;
+        while self.eat('*') {
+            lhs = Expr::Mul(Box::new(lhs), Box::new(self.factor()?));
+        }
+        Ok(lhs)
+    }
+
+    fn factor(&mut self) -> Result<Expr, Error> {
+        let column = self.column();
+        match self.peek() {
+            Some(Tok::Num(n)) => {
+                self.pos += 1;
+                Ok(Expr::Num(*n))
+            }
+            Some(Tok::Ident(name)) => {
+                self.pos += 1;
+                Ok(Expr::Sym(name.clone(), column))
+            }
+            Some(Tok::Punct('-')) => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.factor()?)))
+            }
+            Some(Tok::Punct('+')) => {
+                self.pos += 1;
+                self.factor()
+            }
+            Some(Tok::Punct('(')) => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, Error> {
+        let column = self.column();
+        let (mode, expr) = match self.peek() {
+            Some(Tok::Punct('[')) => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                self.expect(']')?;
+                (ParamMode::Position, expr)
+            }
+            Some(Tok::Punct('#')) => {
+                self.pos += 1;
+                (ParamMode::Immediate, self.expr()?)
+            }
+            Some(Tok::Ident(name)) if name == "rb" => {
+                self.pos += 1;
+                match self.peek() {
+                    Some(Tok::Punct('+')) | Some(Tok::Punct('-')) => {
+                        (ParamMode::Relative, self.expr()?)
+                    }
+                    _ => return Err(self.unexpected()),
+                }
+            }
+            _ => return Err(self.unexpected()),
+        };
+        Ok(Operand { mode, expr, column })
+    }
+
+    fn list<T>(&mut self, item: fn(&mut Self) -> Result<T, Error>) -> Result<Vec<T>, Error> {
+        let mut items = Vec::new();
+        if self.at_end() {
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if !self.eat(',') {
+                break;
+            }
+        }
+        if !self.at_end() {
+            return Err(self.unexpected());
+        }
+        Ok(items)
+    }
+}
+
+struct Assembler {
+    symbols: HashMap<String, i128>,
+    addr: usize,
+}
+
+impl Assembler {
+    fn define(&mut self, name: &str, value: i128, line: usize, column: usize) -> Result<(), Error> {
+        if self.symbols.insert(name.to_string(), value).is_some() {
+            return Err(Error {
+                line,
+                column,
+                kind: ErrorKind::DuplicateSymbol(name.to_string()),
+            });
+        }
+        Ok(())
+    }
+
+    fn eval(&self, expr: &Expr, line: usize, column: usize) -> Result<i128, Error> {
+        let overflow = || Error {
+            line,
+            column,
+            kind: ErrorKind::Overflow,
+        };
+        Ok(match expr {
+            Expr::Num(n) => *n,
+            Expr::Sym(name, sym_column) => match self.symbols.get(name) {
+                Some(value) => *value,
+                None => {
+                    return Err(Error {
+                        line,
+                        column: *sym_column,
+                        kind: ErrorKind::UndefinedSymbol(name.clone()),
+                    })
+                }
+            },
+            Expr::Neg(e) => self
+                .eval(e, line, column)?
+                .checked_neg()
+                .ok_or_else(overflow)?,
+            Expr::Add(a, b) => self
+                .eval(a, line, column)?
+                .checked_add(self.eval(b, line, column)?)
+                .ok_or_else(overflow)?,
+            Expr::Sub(a, b) => self
+                .eval(a, line, column)?
+                .checked_sub(self.eval(b, line, column)?)
+                .ok_or_else(overflow)?,
+            Expr::Mul(a, b) => self
+                .eval(a, line, column)?
+                .checked_mul(self.eval(b, line, column)?)
+                .ok_or_else(overflow)?,
+        })
+    }
+
+    fn eval_word(&self, expr: &Expr, line: usize, column: usize) -> Result<Word, Error> {
+        let value = self.eval(expr, line, column)?;
+        Word::try_from(value).map_err(|_| Error {
+            line,
+            column,
+            kind: ErrorKind::Overflow,
+        })
+    }
+
+    /// Parses a line, defining its labels and constants. Returns the
+    /// statement to emit in the second pass, if any.
+    fn parse_line(&mut self, p: &mut Parser) -> Result<Option<Stmt>, Error> {
+        while let (Some(Tok::Ident(name)), Some(Tok::Punct(':'))) = (p.peek(), p.peek_at(1)) {
+            let column = p.column();
+            self.define(name, self.addr as i128, p.line, column)?;
+            p.pos += 2;
+        }
+        if p.at_end() {
+            return Ok(None);
+        }
+
+        let column = p.column();
+        let name = p.ident()?;
+        let stmt = match name {
+            ".data" => {
+                let values = p.list(Parser::located_expr)?;
+                self.addr += values.len();
+                Stmt::Data(values)
+            }
+            ".fill" => {
+                let count_column = p.column();
+                let count = p.expr()?;
+                p.expect(',')?;
+                let (value, value_column) = p.located_expr()?;
+                if !p.at_end() {
+                    return Err(p.unexpected());
+                }
+                let count = self.eval_word(&count, p.line, count_column)?;
+                if count < 0 {
+                    return Err(Error {
+                        line: p.line,
+                        column: count_column,
+                        kind: ErrorKind::NegativeFill(count),
+                    });
+                }
+                let count = usize::try_from(count).unwrap_or(usize::MAX);
+                self.addr = self
+                    .addr
+                    .checked_add(count)
+                    .filter(|&addr| addr <= MAX_LEN)
+                    .ok_or(Error {
+                        line: p.line,
+                        column: count_column,
+                        kind: ErrorKind::TooLarge,
+                    })?;
+                Stmt::Fill(count, value, value_column)
+            }
+            ".equ" => {
+                let name_column = p.column();
+                let name = p.ident()?;
+                p.expect(',')?;
+                let (value, value_column) = p.located_expr()?;
+                if !p.at_end() {
+                    return Err(p.unexpected());
+                }
+                let value = self.eval(&value, p.line, value_column)?;
+                self.define(name, value, p.line, name_column)?;
+                return Ok(None);
+            }
+            _ if name.starts_with('.') => {
+                return Err(Error {
+                    line: p.line,
+                    column,
+                    kind: ErrorKind::UnknownDirective(name.to_string()),
+                })
+            }
+            _ => {
+                let op = match OPERATORS.iter().find(|op| op.mnemonic() == name) {
+                    Some(op) => *op,
+                    None => {
+                        return Err(Error {
+                            line: p.line,
+                            column,
+                            kind: ErrorKind::UnknownMnemonic(name.to_string()),
+                        })
+                    }
+                };
+                let operands = p.list(Parser::operand)?;
+                let inst = Instruction {
+                    op,
+                    m1: ParamMode::Position,
+                    m2: ParamMode::Position,
+                    m3: ParamMode::Position,
+                };
+                let expected = inst.increment() - 1;
+                if operands.len() != expected {
+                    return Err(Error {
+                        line: p.line,
+                        column,
+                        kind: ErrorKind::OperandCount {
+                            expected,
+                            found: operands.len(),
+                        },
+                    });
+                }
+                if let Some(dest) = inst.dest_param() {
+                    if operands[dest].mode == ParamMode::Immediate {
+                        return Err(Error {
+                            line: p.line,
+                            column: operands[dest].column,
+                            kind: ErrorKind::ImmediateDestination,
+                        });
+                    }
+                }
+                self.addr += inst.increment();
+                Stmt::Instruction(op, operands)
+            }
+        };
+        Ok(Some(stmt))
+    }
+
+    fn emit(&self, stmt: &Stmt, line: usize, code: &mut Vec<Word>) -> Result<(), Error> {
+        match stmt {
+            Stmt::Instruction(op, operands) => {
+                let mut modes = [ParamMode::Position; 3];
+                for (mode, operand) in modes.iter_mut().zip(operands.iter()) {
+                    *mode = operand.mode;
+                }
+                let inst = Instruction {
+                    op: *op,
+                    m1: modes[0],
+                    m2: modes[1],
+                    m3: modes[2],
+                };
+                code.push(inst.encode());
+                for operand in operands {
+                    code.push(self.eval_word(&operand.expr, line, operand.column)?);
+                }
+            }
+            Stmt::Data(values) => {
+                for (value, column) in values {
+                    code.push(self.eval_word(value, line, *column)?);
+                }
+            }
+            Stmt::Fill(count, value, column) => {
+                let value = self.eval_word(value, line, *column)?;
+                code.resize(code.len() + count, value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Assembles `source` into an Intcode program.
+pub fn assemble(source: &str) -> Result<Vec<Word>, Error> {
+    let mut asm = Assembler {
+        symbols: HashMap::new(),
+        addr: 0,
+    };
+    let mut stmts = Vec::new();
+    for (idx, line) in source.lines().enumerate() {
+        let tokens = tokenize(line, idx + 1)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            line: idx + 1,
+            line_len: line.chars().count(),
+        };
+        if let Some(stmt) = asm.parse_line(&mut parser)? {
+            stmts.push((idx + 1, stmt));
+        }
+    }
+
+    let mut code = Vec::with_capacity(asm.addr);
+    for (line, stmt) in stmts.iter() {
+        asm.emit(stmt, *line, &mut code)?;
+    }
+    Ok(code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::disasm::{disassemble, DisasmLine};
+    use crate::Intcode;
+
+    fn round_trip(code: &[Word]) {
+        let source: Vec<_> = disassemble(code)
+            .iter()
+            .map(DisasmLine::to_string)
+            .collect();
+        assert_eq!(assemble(&source.join("\n")).unwrap(), code);
+    }
+
+    fn error_at(source: &str) -> (usize, usize, ErrorKind) {
+        let e = assemble(source).unwrap_err();
+        (e.line, e.column, e.kind)
+    }
+
+    #[test]
+    fn test_instructions() {
+        let code = assemble("in [3]\neq #-1, #8, [3]\nout [3]\nhlt").unwrap();
+        assert_eq!(code, vec![3, 3, 1108, -1, 8, 3, 4, 3, 99]);
+    }
+
+    #[test]
+    fn test_relative() {
+        let code = assemble("arb #1\nout rb-1\nadd [100], #1, rb+3\nhlt").unwrap();
+        assert_eq!(code, vec![109, 1, 204, -1, 21001, 100, 1, 3, 99]);
+    }
+
+    #[test]
+    fn test_labels_and_directives() {
+        let source = "
+            ; count down from START
+                    .equ START, 3
+                    add #START, #0, [counter]
+            loop:   out [counter]
+                    add [counter], #-1, [counter]
+                    jt [counter], #loop
+                    hlt
+            counter: .data 0
+            table:  .fill 2 * 2, table - counter
+        ";
+        let mut code = assemble(source).unwrap();
+        assert_eq!(&code[..4], &[1101, 3, 0, 14]);
+        assert_eq!(&code[4..6], &[4, 14]);
+        assert_eq!(&code[10..13], &[1005, 14, 4]);
+        assert_eq!(&code[14..], &[0, 1, 1, 1, 1]);
+
+        let mut output = Vec::new();
+        code.run(&[], &mut |r| output.push(r)).unwrap();
+        assert_eq!(output, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_expressions() {
+        let code = assemble(".equ A, 0x10\n.data A * (2 + 1), -A - -1, rb_x\nrb_x: hlt").unwrap();
+        assert_eq!(code, vec![48, -15, 3, 99]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            error_at("hlt\n  foo [1]"),
+            (2, 3, ErrorKind::UnknownMnemonic("foo".to_string()))
+        );
+        assert_eq!(
+            error_at("add [1], #2, #3"),
+            (1, 14, ErrorKind::ImmediateDestination)
+        );
+        assert_eq!(
+            error_at("out [x]"),
+            (1, 6, ErrorKind::UndefinedSymbol("x".to_string()))
+        );
+        assert_eq!(
+            error_at("jt [1]"),
+            (
+                1,
+                1,
+                ErrorKind::OperandCount {
+                    expected: 2,
+                    found: 1
+                }
+            )
+        );
+        assert_eq!(
+            error_at("a: hlt\na: hlt"),
+            (2, 1, ErrorKind::DuplicateSymbol("a".to_string()))
+        );
+        assert_eq!(error_at("out [1"), (1, 7, ErrorKind::UnexpectedEnd));
+        assert_eq!(error_at("out @1"), (1, 5, ErrorKind::UnexpectedChar('@')));
+        let too_big = if cfg!(feature = "i128") {
+            ".data 1, 0x40000000000000000000000000000000 * 2"
+        } else {
+            ".data 1, 0x8000000000000000"
+        };
+        assert_eq!(error_at(too_big), (1, 10, ErrorKind::Overflow));
+        assert_eq!(
+            error_at(".data -(-0x7fffffffffffffffffffffffffffffff - 1)"),
+            (1, 7, ErrorKind::Overflow)
+        );
+        assert_eq!(
+            error_at("hlt\n.fill 0x7fffffffffffffff, 0"),
+            (2, 7, ErrorKind::TooLarge)
+        );
+        assert_eq!(
+            error_at(".fill 0x1000000, 0\n.fill 1, 0"),
+            (2, 7, ErrorKind::TooLarge)
+        );
+        assert_eq!(
+            error_at(".fill x, 0\nx: hlt").2,
+            ErrorKind::UndefinedSymbol("x".to_string())
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(&[3, 3, 1108, -1, 8, 3, 4, 3, 99]);
+        round_trip(&[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9]);
+        round_trip(&[
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ]);
+        round_trip(&[1102, 34915192, 34915192, 7, 4, 7, 99, 0]);
+        round_trip(&[104, 1125899906842624, 99]);
+        round_trip(&[1105, 1, 7, 0, -1, 1099, 5, 99, 11101, 1, 1, 0, 1101, 1, 1]);
+        let (min, max) = (Word::from(i64::MIN), Word::from(i64::MAX));
+        round_trip(&[min, max, 204, min, 99]);
+    }
+}
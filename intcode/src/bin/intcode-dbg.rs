@@ -0,0 +1,197 @@
+use intcode::debug::{Access, Debugger, Stop};
+use intcode::memory::Memory;
+use intcode::Program;
+use intcode::{disasm, load};
+
+use std::env;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+Commands:
+  s, step [N]          execute N instructions (default 1)
+  c, continue          run until a breakpoint, watchpoint, input or halt
+  b, break ADDR        set a breakpoint
+  d, delete ADDR       remove a breakpoint or watchpoint
+  w, watch ADDR [r|w]  stop after ADDR is read and/or written
+  x/N ADDR             show N memory cells starting at ADDR
+  i, input V...        queue input values
+  l, list [N]          disassemble N instructions from the pc (default 5)
+  r, regs              show the pc and relative base
+  info                 list breakpoints and watchpoints
+  q, quit              exit
+Addresses are decimal or 0x-prefixed hexadecimal.";
+
+fn parse_addr(s: Option<&str>) -> Result<usize, String> {
+    let s = s.ok_or("Missing address")?;
+    let addr = match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    addr.map_err(|_| format!("Invalid address: {}", s))
+}
+
+fn parse_count(s: Option<&str>, default: usize) -> Result<usize, String> {
+    match s {
+        Some(s) => s.parse().map_err(|_| format!("Invalid count: {}", s)),
+        None => Ok(default),
+    }
+}
+
+/// Prints `count` lines of disassembly starting at `addr`. Only the cells
+/// of each instruction are read, so far apart addresses in memory cost
+/// nothing.
+fn list(program: &Program, addr: usize, count: usize) {
+    let len = program.memory().len();
+    let mut addr = addr;
+    for _ in 0..count {
+        if addr >= len {
+            break;
+        }
+        // No instruction is longer than 4 cells.
+        let cells: Vec<_> = (addr..addr.saturating_add(4).min(len))
+            .map(|a| program.peek(a))
+            .collect();
+        let marker = if addr == program.pc() { "=>" } else { "  " };
+        match disasm::decode_at(&cells, 0) {
+            Some(line) => {
+                println!("{} {:04}: {}", marker, addr, line);
+                addr += line.len();
+            }
+            None => {
+                println!("{} {:04}: .data {}", marker, addr, cells[0]);
+                addr += 1;
+            }
+        }
+    }
+}
+
+fn report(dbg: &mut Debugger, stop: Stop) {
+    for value in dbg.take_output() {
+        println!("output: {}", value);
+    }
+    match stop {
+        Stop::Step => (),
+        Stop::Breakpoint(addr) => println!("breakpoint at {:04}", addr),
+        Stop::Watchpoint { addr, access } => {
+            println!(
+                "watchpoint {:?} at {} = {}",
+                access,
+                addr,
+                dbg.program().peek(addr)
+            )
+        }
+        Stop::NeedsInput => println!("waiting for input"),
+        Stop::Halted => println!("program halted"),
+    }
+    list(dbg.program(), dbg.program().pc(), 1);
+}
+
+fn execute(dbg: &mut Debugger, line: &str) -> Result<bool, String> {
+    let mut words = line.split_whitespace();
+    let cmd = match words.next() {
+        Some(cmd) => cmd,
+        None => return Ok(true),
+    };
+    match cmd {
+        "s" | "step" => {
+            let mut stop = Stop::Step;
+            for _ in 0..parse_count(words.next(), 1)? {
+                stop = dbg.step().map_err(|e| e.to_string())?;
+                if stop != Stop::Step {
+                    break;
+                }
+            }
+            report(dbg, stop);
+        }
+        "c" | "continue" => {
+            let stop = dbg.run().map_err(|e| e.to_string())?;
+            report(dbg, stop);
+        }
+        "b" | "break" => dbg.add_breakpoint(parse_addr(words.next())?),
+        "d" | "delete" => {
+            let addr = parse_addr(words.next())?;
+            let removed = dbg.remove_breakpoint(addr) | dbg.remove_watchpoint(addr);
+            if !removed {
+                return Err(format!("No breakpoint or watchpoint at {}", addr));
+            }
+        }
+        "w" | "watch" => {
+            let addr = parse_addr(words.next())?;
+            let access = match words.next() {
+                None | Some("rw") => Access::ReadWrite,
+                Some("r") => Access::Read,
+                Some("w") => Access::Write,
+                Some(other) => return Err(format!("Invalid access: {}", other)),
+            };
+            dbg.add_watchpoint(addr, access);
+        }
+        "i" | "input" => {
+            for word in words {
+                let value = word
+                    .parse()
+                    .map_err(|_| format!("Invalid input value: {}", word))?;
+                dbg.push_input(value);
+            }
+        }
+        "l" | "list" => {
+            let count = parse_count(words.next(), 5)?;
+            list(dbg.program(), dbg.program().pc(), count);
+        }
+        "r" | "regs" => {
+            let program = dbg.program();
+            println!(
+                "pc = {} rb = {} halted = {}",
+                program.pc(),
+                program.rel_base(),
+                program.is_done()
+            );
+        }
+        "info" => {
+            for addr in dbg.breakpoints() {
+                println!("breakpoint {:04}", addr);
+            }
+            for (addr, access) in dbg.watchpoints() {
+                println!("watchpoint {:04} {:?}", addr, access);
+            }
+        }
+        "q" | "quit" => return Ok(false),
+        "h" | "help" => println!("{}", HELP),
+        _ if cmd == "x" || cmd.starts_with("x/") => {
+            let count = parse_count(cmd.strip_prefix("x/"), 1)?;
+            let addr = parse_addr(words.next())?;
+            // Stops at the end of the address space rather than overflow.
+            let last = addr.saturating_add(count);
+            for start in (addr..last).step_by(8) {
+                let end = start.saturating_add(8).min(last);
+                let values: Vec<_> = (start..end)
+                    .map(|a| dbg.program().peek(a).to_string())
+                    .collect();
+                println!("{:04}: {}", start, values.join(" "));
+            }
+        }
+        _ => return Err(format!("Unknown command: {} (try help)", cmd)),
+    }
+    Ok(true)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let code = load::from_path(env::args().nth(1).expect("Specify program file"))?;
+    let mut dbg = Debugger::new(Program::new(&code));
+    list(dbg.program(), 0, 1);
+
+    let stdin = io::stdin();
+    loop {
+        print!("(icdb) ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        match execute(&mut dbg, &line) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    Ok(())
+}
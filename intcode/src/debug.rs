@@ -0,0 +1,250 @@
+//! Breakpoints and watchpoints on top of `Program::step`.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::convert::TryFrom;
+
+use crate::memory::{Dense, Memory};
+use crate::{Error, Instruction, ParamMode, Program, RunState, Word};
+
+/// The kind of memory access a watchpoint triggers on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    fn matches(self, other: Access) -> bool {
+        self == Access::ReadWrite || self == other
+    }
+}
+
+/// The reason the debugger stopped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stop {
+    /// A single instruction was executed.
+    Step,
+    /// The program counter reached a breakpoint.
+    Breakpoint(usize),
+    /// The last instruction accessed a watched address.
+    Watchpoint {
+        addr: usize,
+        access: Access,
+    },
+    /// The program needs input and the input queue is empty.
+    NeedsInput,
+    Halted,
+}
+
+#[derive(Debug)]
+pub struct Debugger<M = Dense> {
+    program: Program<M>,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeMap<usize, Access>,
+    input: VecDeque<Word>,
+    output: Vec<Word>,
+}
+
+impl<M: Memory> Debugger<M> {
+    pub fn new(program: Program<M>) -> Debugger<M> {
+        Debugger {
+            program,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+            input: VecDeque::new(),
+            output: Vec::new(),
+        }
+    }
+
+    pub fn program(&self) -> &Program<M> {
+        &self.program
+    }
+
+    pub fn add_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.insert(addr);
+    }
+
+    /// Returns `false` if there was no breakpoint at `addr`.
+    pub fn remove_breakpoint(&mut self, addr: usize) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    pub fn add_watchpoint(&mut self, addr: usize, access: Access) {
+        self.watchpoints.insert(addr, access);
+    }
+
+    /// Returns `false` if there was no watchpoint on `addr`.
+    pub fn remove_watchpoint(&mut self, addr: usize) -> bool {
+        self.watchpoints.remove(&addr).is_some()
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = (usize, Access)> + '_ {
+        self.watchpoints
+            .iter()
+            .map(|(&addr, &access)| (addr, access))
+    }
+
+    /// Queues a value for the program's next `In` instruction.
+    pub fn push_input(&mut self, value: Word) {
+        self.input.push_back(value);
+    }
+
+    /// Returns the values output since the last call.
+    pub fn take_output(&mut self) -> Vec<Word> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Executes a single instruction.
+    pub fn step(&mut self) -> Result<Stop, Error> {
+        let (reads, write) = self.accesses();
+        let input = &mut self.input;
+        let state = self.program.step(&mut || input.pop_front())?;
+        match state {
+            Some(RunState::NeedsInput) => return Ok(Stop::NeedsInput),
+            Some(RunState::Halted) => return Ok(Stop::Halted),
+            Some(RunState::Output(value)) => self.output.push(value),
+            None => (),
+        }
+
+        let accessed = reads
+            .into_iter()
+            .map(|addr| (addr, Access::Read))
+            .chain(write.map(|addr| (addr, Access::Write)));
+        for (addr, access) in accessed {
+            match self.watchpoints.get(&addr) {
+                Some(watch) if watch.matches(access) => {
+                    return Ok(Stop::Watchpoint { addr, access })
+                }
+                _ => (),
+            }
+        }
+        Ok(Stop::Step)
+    }
+
+    /// Runs until a breakpoint or watchpoint is hit, the program needs
+    /// input or halts. The instruction at the current program counter is
+    /// always executed, so continuing from a breakpoint makes progress.
+    pub fn run(&mut self) -> Result<Stop, Error> {
+        loop {
+            match self.step()? {
+                Stop::Step => (),
+                stop => return Ok(stop),
+            }
+            let pc = self.program.pc();
+            if self.breakpoints.contains(&pc) {
+                return Ok(Stop::Breakpoint(pc));
+            }
+        }
+    }
+
+    /// Addresses the instruction at the program counter reads from and
+    /// writes to.
+    fn accesses(&self) -> (Vec<usize>, Option<usize>) {
+        let pc = self.program.pc();
+        let inst = match Instruction::try_from(self.program.peek(pc)) {
+            Ok(inst) => inst,
+            Err(_) => return (Vec::new(), None),
+        };
+        let mut reads = Vec::new();
+        let mut write = None;
+        for (idx, mode) in inst.modes()[..inst.increment() - 1].iter().enumerate() {
+            let param = self.program.peek(pc + idx + 1);
+            let addr = match mode {
+                ParamMode::Position => Some(param),
+                ParamMode::Relative => param.checked_add(self.program.rel_base()),
+                ParamMode::Immediate => continue,
+            };
+            // Addresses that overflow or are negative fail in the program.
+            let addr = match addr.and_then(|addr| usize::try_from(addr).ok()) {
+                Some(addr) => addr,
+                None => continue,
+            };
+            if inst.dest_param() == Some(idx) {
+                write = Some(addr);
+            } else {
+                reads.push(addr);
+            }
+        }
+        (reads, write)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    // Reads a number and counts down from it.
+    pub(crate) const COUNTDOWN: [Word; 15] =
+        [3, 14, 4, 14, 1001, 14, -1, 14, 1005, 14, 2, 99, 0, 0, 0];
+
+    #[test]
+    fn test_step() {
+        let mut dbg = Debugger::new(Program::new(&COUNTDOWN));
+        assert_eq!(dbg.step().unwrap(), Stop::NeedsInput);
+        assert_eq!(dbg.program().pc(), 0);
+        dbg.push_input(2);
+        assert_eq!(dbg.step().unwrap(), Stop::Step);
+        assert_eq!(dbg.program().pc(), 2);
+        assert_eq!(dbg.program().peek(14), 2);
+        assert_eq!(dbg.step().unwrap(), Stop::Step);
+        assert_eq!(dbg.take_output(), vec![2]);
+        assert!(dbg.take_output().is_empty());
+
+        let mut dbg = Debugger::new(Program::new(&[109, Word::MAX, 204, 1, 99]));
+        assert_eq!(dbg.step().unwrap(), Stop::Step);
+        assert!(matches!(dbg.step(), Err(Error::Overflow(_))));
+    }
+
+    #[test]
+    fn test_breakpoint() {
+        let mut dbg = Debugger::new(Program::new(&COUNTDOWN));
+        dbg.push_input(3);
+        dbg.add_breakpoint(4);
+        assert_eq!(dbg.run().unwrap(), Stop::Breakpoint(4));
+        assert_eq!(dbg.run().unwrap(), Stop::Breakpoint(4));
+        assert_eq!(dbg.take_output(), vec![3, 2]);
+        assert!(dbg.remove_breakpoint(4));
+        assert!(!dbg.remove_breakpoint(4));
+        assert_eq!(dbg.run().unwrap(), Stop::Halted);
+        assert_eq!(dbg.take_output(), vec![1]);
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let mut dbg = Debugger::new(Program::new(&COUNTDOWN));
+        dbg.push_input(2);
+        dbg.add_watchpoint(14, Access::Write);
+        assert_eq!(
+            dbg.run().unwrap(),
+            Stop::Watchpoint {
+                addr: 14,
+                access: Access::Write
+            }
+        );
+        assert_eq!(dbg.program().pc(), 2);
+        assert_eq!(
+            dbg.run().unwrap(),
+            Stop::Watchpoint {
+                addr: 14,
+                access: Access::Write
+            }
+        );
+        assert_eq!(dbg.program().pc(), 8);
+        assert_eq!(dbg.program().peek(14), 1);
+
+        dbg.add_watchpoint(14, Access::Read);
+        assert_eq!(
+            dbg.run().unwrap(),
+            Stop::Watchpoint {
+                addr: 14,
+                access: Access::Read
+            }
+        );
+        assert_eq!(dbg.program().pc(), 2);
+    }
+}
@@ -0,0 +1,217 @@
+//! Disassembler for Intcode programs.
+//!
+//! `disassemble` walks a program from address 0 and decodes every word it
+//! can into an instruction. Words that do not decode into a canonical
+//! instruction, or instructions that would run past the end of the program,
+//! are collected into `.data` runs instead.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::{Instruction, ParamMode, Word};
+
+/// Maximum number of values in a single `.data` line.
+const DATA_PER_LINE: usize = 8;
+
+/// A decoded instruction parameter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Operand {
+    pub mode: ParamMode,
+    pub value: Word,
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mode {
+            ParamMode::Position => write!(f, "[{}]", self.value),
+            ParamMode::Immediate => write!(f, "#{}", self.value),
+            ParamMode::Relative if self.value < 0 => write!(f, "rb-{}", self.value.unsigned_abs()),
+            ParamMode::Relative => write!(f, "rb+{}", self.value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LineKind {
+    Instruction {
+        inst: Instruction,
+        operands: Vec<Operand>,
+    },
+    Data(Vec<Word>),
+}
+
+/// One line of a disassembly listing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisasmLine {
+    /// Address of the first word covered by the line.
+    pub addr: usize,
+    pub kind: LineKind,
+}
+
+impl DisasmLine {
+    /// Number of words covered by the line.
+    pub fn len(&self) -> usize {
+        match &self.kind {
+            LineKind::Instruction { inst, .. } => inst.increment(),
+            LineKind::Data(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn instruction(&self) -> Option<&Instruction> {
+        match &self.kind {
+            LineKind::Instruction { inst, .. } => Some(inst),
+            LineKind::Data(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for DisasmLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, values): (_, Vec<String>) = match &self.kind {
+            LineKind::Instruction { inst, operands } => (
+                inst.op.mnemonic(),
+                operands.iter().map(Operand::to_string).collect(),
+            ),
+            LineKind::Data(values) => (".data", values.iter().map(Word::to_string).collect()),
+        };
+        if values.is_empty() {
+            write!(f, "{}", name)
+        } else {
+            write!(f, "{} {}", name, values.join(", "))
+        }
+    }
+}
+
+/// Decodes the instruction at `addr`. Returns `None` if the word at `addr`
+/// is not a canonically encoded instruction, if the instruction writes to
+/// an immediate parameter or if its parameters extend past the end of
+/// `code`.
+pub fn decode_at(code: &[Word], addr: usize) -> Option<DisasmLine> {
+    let word = *code.get(addr)?;
+    let inst = Instruction::try_from(word).ok()?;
+    if inst.encode() != word {
+        return None;
+    }
+    let params = code.get(addr + 1..addr + inst.increment())?;
+    if let Some(dest) = inst.dest_param() {
+        if inst.modes()[dest] == ParamMode::Immediate {
+            return None;
+        }
+    }
+    let operands = params
+        .iter()
+        .zip(inst.modes().iter())
+        .map(|(&value, &mode)| Operand { mode, value })
+        .collect();
+    Some(DisasmLine {
+        addr,
+        kind: LineKind::Instruction { inst, operands },
+    })
+}
+
+/// Disassembles the whole program.
+pub fn disassemble(code: &[Word]) -> Vec<DisasmLine> {
+    let mut lines: Vec<DisasmLine> = Vec::new();
+    let mut addr = 0;
+    while addr < code.len() {
+        if let Some(line) = decode_at(code, addr) {
+            addr += line.len();
+            lines.push(line);
+            continue;
+        }
+        match lines.last_mut() {
+            Some(DisasmLine {
+                kind: LineKind::Data(values),
+                ..
+            }) if values.len() < DATA_PER_LINE => values.push(code[addr]),
+            _ => lines.push(DisasmLine {
+                addr,
+                kind: LineKind::Data(vec![code[addr]]),
+            }),
+        }
+        addr += 1;
+    }
+    lines
+}
+
+/// Renders a listing with the address and raw words of every line.
+pub fn listing(code: &[Word]) -> String {
+    let mut out = String::new();
+    for line in disassemble(code) {
+        let raw: Vec<_> = code[line.addr..line.addr + line.len()]
+            .iter()
+            .map(Word::to_string)
+            .collect();
+        out.push_str(&format!(
+            "{:04}: {:<32} ; {}\n",
+            line.addr,
+            line.to_string(),
+            raw.join(",")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Operator;
+
+    fn text(code: &[Word]) -> Vec<String> {
+        disassemble(code)
+            .iter()
+            .map(DisasmLine::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_instructions() {
+        let code = [3, 3, 1108, -1, 8, 3, 4, 3, 99];
+        assert_eq!(
+            text(&code),
+            vec!["in [3]", "eq #-1, #8, [3]", "out [3]", "hlt"]
+        );
+    }
+
+    #[test]
+    fn test_relative() {
+        let code = [109, 1, 204, -1, 21001, 100, 1, 3, 99];
+        assert_eq!(
+            text(&code),
+            vec!["arb #1", "out rb-1", "add [100], #1, rb+3", "hlt"]
+        );
+    }
+
+    #[test]
+    fn test_data() {
+        let code = [1105, 1, 7, 0, -1, 1099, 5, 99];
+        let lines = disassemble(&code);
+        assert_eq!(lines[1].kind, LineKind::Data(vec![0, -1, 1099, 5]));
+        assert_eq!(lines[1].addr, 3);
+        assert_eq!(lines[2].addr, 7);
+        assert_eq!(
+            lines[2].instruction().map(|inst| inst.op),
+            Some(Operator::Break)
+        );
+    }
+
+    #[test]
+    fn test_truncated_and_immediate_write() {
+        assert_eq!(text(&[1101, 1, 1]), vec![".data 1101, 1, 1"]);
+        assert_eq!(text(&[11101, 1, 1, 0]), vec![".data 11101, 1, 1, 0"]);
+    }
+
+    #[test]
+    fn test_listing() {
+        let listing = listing(&[1102, 34915192, 34915192, 7, 4, 7, 99, 0]);
+        let lines: Vec<_> = listing.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("0000: mul #34915192, #34915192, [7]"));
+        assert!(lines[0].ends_with("; 1102,34915192,34915192,7"));
+        assert!(lines[3].starts_with("0007: .data 0"));
+    }
+}
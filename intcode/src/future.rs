@@ -0,0 +1,260 @@
+//! Drives programs as futures. Enabled by the `async` feature.
+//!
+//! `drive` runs a single program with its input taken from a `Stream` and
+//! its output fed into a `Sink`, awaiting the stream whenever the program
+//! executes an `In` instruction. `run` connects the machines of a `Network`
+//! with unbounded channels and polls them on the current thread, which lets
+//! it tell when every machine is waiting for input nobody will send.
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::task::{waker, ArcWake};
+use futures::{Sink, SinkExt, Stream, StreamExt};
+
+use crate::memory::Memory;
+use crate::network::{Error, MachineReport, MachineState, Network, Report};
+use crate::{Program, RunState, Word};
+
+/// Runs `program` until it halts or `input` ends while the program is
+/// waiting for a value. Returns `MachineState::Halted` or
+/// `MachineState::Blocked` respectively.
+///
+/// Values that `output` refuses, for example because the receiving end of
+/// a channel was dropped, are discarded.
+pub async fn drive<M, I, O>(
+    program: &mut Program<M>,
+    input: &mut I,
+    output: &mut O,
+) -> Result<MachineState, crate::Error>
+where
+    M: Memory,
+    I: Stream<Item = Word> + Unpin,
+    O: Sink<Word> + Unpin,
+{
+    let mut next = None;
+    loop {
+        match program.run_partial(&mut || next.take())? {
+            RunState::Output(value) => {
+                let _ = output.send(value).await;
+            }
+            RunState::NeedsInput => match input.next().await {
+                Some(value) => next = Some(value),
+                None => return Ok(MachineState::Blocked),
+            },
+            RunState::Halted => return Ok(MachineState::Halted),
+        }
+    }
+}
+
+/// Output of a machine in a network: forwarded to the connected machines
+/// and recorded for the report. The report is shared with `run`, which
+/// still needs it when the machine never finishes.
+struct Outputs {
+    targets: Vec<UnboundedSender<Word>>,
+    report: Rc<RefCell<MachineReport>>,
+}
+
+impl Sink<Word> for Outputs {
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, value: Word) -> Result<(), Infallible> {
+        let outputs = self.get_mut();
+        let mut report = outputs.report.borrow_mut();
+        report.last_output = Some(value);
+        if outputs.targets.is_empty() {
+            report.output.push(value);
+        }
+        for target in outputs.targets.iter() {
+            // The receiving machine may have finished already.
+            let _ = target.unbounded_send(value);
+        }
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Set when a machine's future can make progress.
+struct Wake(AtomicBool);
+
+impl ArcWake for Wake {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+type MachineFuture = Pin<Box<dyn Future<Output = Result<MachineState, crate::Error>>>>;
+
+/// Runs the machines of `network` as futures until every one of them has
+/// halted or is blocked on input. Fails with the error of the first machine
+/// that failed.
+///
+/// Panics if the network uses packet routes, idle input or a NAT, which
+/// only make sense with the round-robin scheduler of `Network::run`.
+pub fn run<M: Memory + 'static>(network: Network<M>) -> Result<Report, Error> {
+    let nodes = network.into_nodes();
+    let (senders, receivers): (Vec<_>, Vec<_>) = nodes.iter().map(|_| mpsc::unbounded()).unzip();
+    for (node, sender) in nodes.iter().zip(senders.iter()) {
+        for &value in node.input.iter() {
+            sender.unbounded_send(value).unwrap();
+        }
+    }
+
+    let mut futures: Vec<Option<MachineFuture>> = Vec::with_capacity(nodes.len());
+    let mut reports = Vec::with_capacity(nodes.len());
+    for (node, mut receiver) in nodes.into_iter().zip(receivers) {
+        // Machines still pending at the end are waiting for input that
+        // will never come.
+        let report = Rc::new(RefCell::new(MachineReport {
+            state: MachineState::Blocked,
+            output: Vec::new(),
+            last_output: None,
+        }));
+        let mut outputs = Outputs {
+            targets: node.targets.iter().map(|&to| senders[to].clone()).collect(),
+            report: report.clone(),
+        };
+        reports.push(report);
+        let mut program = node.program;
+        futures.push(Some(Box::pin(async move {
+            drive(&mut program, &mut receiver, &mut outputs).await
+        })));
+    }
+    // Machines whose senders are all gone see the end of their input.
+    drop(senders);
+
+    let wakes: Vec<_> = futures
+        .iter()
+        .map(|_| Arc::new(Wake(AtomicBool::new(true))))
+        .collect();
+    loop {
+        let mut progress = false;
+        for id in 0..futures.len() {
+            let future = match futures[id].as_mut() {
+                Some(future) if wakes[id].0.swap(false, Ordering::SeqCst) => future,
+                _ => continue,
+            };
+            progress = true;
+            let waker = waker(wakes[id].clone());
+            if let Poll::Ready(result) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                let state = result.map_err(|error| Error::Machine { id, error })?;
+                reports[id].borrow_mut().state = state;
+                futures[id] = None;
+            }
+        }
+        if !progress {
+            break;
+        }
+    }
+
+    // Drop the futures first, so each report has a single owner left.
+    drop(futures);
+    let machines = reports
+        .into_iter()
+        .map(|report| Rc::try_unwrap(report).unwrap().into_inner())
+        .collect();
+    Ok(Report::new(machines))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::network::test::feedback_loop;
+    use crate::network::Outcome;
+
+    #[test]
+    fn test_drive() {
+        let mut program = Program::new(&[3, 0, 4, 0, 3, 0, 4, 0, 99]);
+        let mut input = futures::stream::iter(vec![4, 5]);
+        let (mut sender, receiver) = mpsc::unbounded();
+        let state = futures::executor::block_on(drive(&mut program, &mut input, &mut sender));
+        assert_eq!(state.unwrap(), MachineState::Halted);
+        drop(sender);
+        let output: Vec<_> = futures::executor::block_on(receiver.collect());
+        assert_eq!(output, vec![4, 5]);
+
+        let mut program = Program::new(&[3, 0, 4, 0, 3, 0, 4, 0, 99]);
+        let mut input = futures::stream::iter(vec![4]);
+        let mut output = futures::sink::drain();
+        let state = futures::executor::block_on(drive(&mut program, &mut input, &mut output));
+        assert_eq!(state.unwrap(), MachineState::Blocked);
+        assert_eq!(program.pc(), 4);
+    }
+
+    #[test]
+    fn test_feedback_loop() {
+        let mut network = feedback_loop(&[9, 8, 7, 6, 5]);
+        assert_eq!(network.run().unwrap(), Outcome::Halted);
+        let expected = network.last_output(4);
+
+        let report = run(feedback_loop(&[9, 8, 7, 6, 5])).unwrap();
+        assert_eq!(report.outcome, Outcome::Halted);
+        assert_eq!(report.machines[4].last_output, expected);
+        assert_eq!(
+            report.machines[4].last_output,
+            crate::threaded::run(feedback_loop(&[9, 8, 7, 6, 5]))
+                .unwrap()
+                .machines[4]
+                .last_output
+        );
+    }
+
+    #[test]
+    fn test_deadlock() {
+        let echo = [3, 0, 4, 0, 99];
+        let network = Network::ring(vec![Program::new(&echo), Program::new(&echo)]);
+        let report = run(network).unwrap();
+        assert_eq!(report.outcome, Outcome::Deadlock(vec![0, 1]));
+
+        let mut network = Network::pipeline(vec![Program::new(&[104, 1, 99]), Program::new(&echo)]);
+        network.add(Program::new(&echo));
+        let report = run(network).unwrap();
+        assert_eq!(report.outcome, Outcome::Deadlock(vec![2]));
+        assert_eq!(report.machines[1].output, vec![1]);
+    }
+
+    #[test]
+    fn test_blocked_output() {
+        // A sink that echoes the source and then waits for input the
+        // halted source never sends.
+        let network = || {
+            Network::pipeline(vec![
+                Program::new(&[104, 3, 99]),
+                Program::new(&[3, 0, 104, 7, 4, 0, 3, 0, 99]),
+            ])
+        };
+        let report = run(network()).unwrap();
+        assert_eq!(report.machines[1].state, MachineState::Blocked);
+        assert_eq!(report.machines[1].output, vec![7, 3]);
+        assert_eq!(report.machines[1].last_output, Some(3));
+        assert_eq!(report, crate::threaded::run(network()).unwrap());
+    }
+
+    #[test]
+    fn test_error() {
+        let network =
+            Network::pipeline(vec![Program::new(&[104, 1, 99]), Program::new(&[3, 0, 77])]);
+        match run(network) {
+            Err(Error::Machine { id: 1, .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -1,11 +1,76 @@
 use std::convert::TryFrom;
 
+pub mod ascii;
+pub mod asm;
+pub mod debug;
+pub mod disasm;
+#[cfg(feature = "async")]
+pub mod future;
+pub mod load;
+pub mod memory;
+pub mod network;
+pub mod snapshot;
+pub mod threaded;
+pub mod trace;
+
+use memory::{Bounded, Dense, LimitExceeded, Memory};
+use snapshot::Snapshot;
+
+use trace::{Event, MemWrite, Observer};
+
+/// The value of a memory cell. Building with the `i128` feature widens
+/// cells for programs whose values do not fit in 64 bits.
+#[cfg(not(feature = "i128"))]
+pub type Word = i64;
+#[cfg(feature = "i128")]
+pub type Word = i128;
+
 #[derive(Debug)]
 pub enum Error {
-    UnknownOpcode(i64),
+    UnknownOpcode(Word),
     UnknownMode(u32),
     UnknownModeChar(char),
     UnexpectedMode(ParamMode),
+    /// An operand resolved to an address below zero.
+    NegativeAddress {
+        addr: Word,
+        fault: Fault,
+    },
+    /// An instruction tried to write through an immediate-mode parameter.
+    ImmediateWrite(Fault),
+    /// A jump, or execution running off the end of memory, moved the
+    /// program counter outside of the program's memory.
+    JumpOutOfRange {
+        target: Word,
+        fault: Fault,
+    },
+    /// An operand resolved to an address past the configured memory limit.
+    MemoryLimit {
+        addr: usize,
+        limit: usize,
+        fault: Fault,
+    },
+    /// An `In` instruction was reached after all input was consumed.
+    InputExhausted(Fault),
+    /// An arithmetic result did not fit in a `Word`. Raised for `Add` and
+    /// `Mul` under `Arithmetic::Checked`, and for relative base and
+    /// relative address computations under every policy.
+    Overflow(Fault),
+}
+
+impl Error {
+    /// Returns the machine state at the point of failure for runtime errors.
+    pub fn fault(&self) -> Option<&Fault> {
+        use Error::*;
+
+        match self {
+            NegativeAddress { fault, .. }
+            | JumpOutOfRange { fault, .. }
+            | MemoryLimit { fault, .. } => Some(fault),
+            ImmediateWrite(fault) | InputExhausted(fault) | Overflow(fault) => Some(fault),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -17,13 +82,47 @@ impl std::fmt::Display for Error {
             UnknownMode(m) => write!(f, "Unknown mode: {}", m),
             UnknownModeChar(c) => write!(f, "Unknown mode: {}", c),
             UnexpectedMode(m) => write!(f, "Unexpected mode: {:?}", m),
+            NegativeAddress { addr, fault } => {
+                write!(f, "Negative address {} at {}", addr, fault)
+            }
+            ImmediateWrite(fault) => write!(f, "Write to immediate parameter at {}", fault),
+            JumpOutOfRange { target, fault } => {
+                write!(f, "Jump out of range to {} at {}", target, fault)
+            }
+            MemoryLimit { addr, limit, fault } => write!(
+                f,
+                "Address {} exceeds memory limit {} at {}",
+                addr, limit, fault
+            ),
+            InputExhausted(fault) => write!(f, "Input exhausted at {}", fault),
+            Overflow(fault) => write!(f, "Arithmetic overflow at {}", fault),
         }
     }
 }
 
 impl std::error::Error for Error {}
 
-#[derive(Clone, Copy, Debug)]
+/// Machine state recorded when a program fails at runtime.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Fault {
+    /// Address of the faulting instruction.
+    pub pc: usize,
+    /// The raw instruction word at `pc`.
+    pub instruction: Word,
+    pub rel_base: Word,
+}
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "pc {} (instruction {}, relative base {})",
+            self.pc, self.instruction, self.rel_base
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Operator {
     Add,
     Mul,
@@ -37,10 +136,10 @@ pub enum Operator {
     Break,
 }
 
-impl TryFrom<i64> for Operator {
+impl TryFrom<Word> for Operator {
     type Error = Error;
 
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
+    fn try_from(code: Word) -> Result<Self, Self::Error> {
         match code {
             1 => Ok(Operator::Add),
             2 => Ok(Operator::Mul),
@@ -57,7 +156,43 @@ impl TryFrom<i64> for Operator {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+impl Operator {
+    pub fn code(&self) -> Word {
+        use Operator::*;
+
+        match self {
+            Add => 1,
+            Mul => 2,
+            In => 3,
+            Out => 4,
+            JumpIfTrue => 5,
+            JumpIfFalse => 6,
+            LessThan => 7,
+            Equals => 8,
+            AdjRelBase => 9,
+            Break => 99,
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        use Operator::*;
+
+        match self {
+            Add => "add",
+            Mul => "mul",
+            In => "in",
+            Out => "out",
+            JumpIfTrue => "jt",
+            JumpIfFalse => "jf",
+            LessThan => "lt",
+            Equals => "eq",
+            AdjRelBase => "arb",
+            Break => "hlt",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ParamMode {
     Position,
     Immediate,
@@ -77,6 +212,16 @@ impl TryFrom<u32> for ParamMode {
     }
 }
 
+impl ParamMode {
+    pub fn code(&self) -> Word {
+        match self {
+            ParamMode::Position => 0,
+            ParamMode::Immediate => 1,
+            ParamMode::Relative => 2,
+        }
+    }
+}
+
 impl TryFrom<char> for ParamMode {
     type Error = Error;
 
@@ -88,7 +233,7 @@ impl TryFrom<char> for ParamMode {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Instruction {
     pub op: Operator,
     pub m1: ParamMode,
@@ -108,208 +253,551 @@ impl Instruction {
             Break => 1,
         }
     }
+
+    pub(crate) fn modes(&self) -> [ParamMode; 3] {
+        [self.m1, self.m2, self.m3]
+    }
+
+    /// Index of the parameter the instruction writes to, if any.
+    pub(crate) fn dest_param(&self) -> Option<usize> {
+        use Operator::*;
+
+        match self.op {
+            Add | Mul | LessThan | Equals => Some(2),
+            In => Some(0),
+            _ => None,
+        }
+    }
+
+    /// Encodes the instruction as a single word. Modes of parameters the
+    /// operator does not take are encoded as zero.
+    pub fn encode(&self) -> Word {
+        self.modes()[..self.increment() - 1]
+            .iter()
+            .rev()
+            .fold(0, |acc, mode| acc * 10 + mode.code())
+            * 100
+            + self.op.code()
+    }
 }
 
-impl TryFrom<i64> for Instruction {
+impl TryFrom<Word> for Instruction {
     type Error = Error;
 
-    fn try_from(instruction: i64) -> Result<Self, Self::Error> {
+    fn try_from(instruction: Word) -> Result<Self, Self::Error> {
         let op = Operator::try_from(instruction % 100)?;
-        let v: Vec<_> = format!("{:05}", instruction).chars().collect();
-        let mode1 = ParamMode::try_from(v[2])?;
-        let mode2 = ParamMode::try_from(v[1])?;
-        let mode3 = ParamMode::try_from(v[0])?;
+        let modes = instruction / 100;
+        let mode = |digits: Word| ParamMode::try_from(u32::try_from(digits).unwrap_or(u32::MAX));
         Ok(Instruction {
-            op: op,
-            m1: mode1,
-            m2: mode2,
-            m3: mode3,
+            op,
+            m1: mode(modes % 10)?,
+            m2: mode(modes / 10 % 10)?,
+            m3: mode(modes / 100)?,
         })
     }
 }
 
+/// Decoded instructions of the loaded program, indexed by address.
+///
+/// Only addresses covered by the loaded code are cached, so programs that
+/// touch far-away memory do not blow up the cache. Any write to a cached
+/// address drops its entry, which keeps self-modifying code correct.
+#[derive(Debug)]
+struct DecodeCache {
+    enabled: bool,
+    entries: Vec<Option<Instruction>>,
+}
+
+impl DecodeCache {
+    fn new(len: usize) -> DecodeCache {
+        DecodeCache {
+            enabled: true,
+            entries: vec![None; len],
+        }
+    }
+
+    fn reset(&mut self, len: usize) {
+        self.entries.clear();
+        self.entries.resize(len, None);
+    }
+
+    fn decode(&mut self, addr: usize, word: Word) -> Result<Instruction, Error> {
+        if !self.enabled {
+            return Instruction::try_from(word);
+        }
+        match self.entries.get_mut(addr) {
+            Some(Some(inst)) => Ok(*inst),
+            Some(entry) => {
+                let inst = Instruction::try_from(word)?;
+                *entry = Some(inst);
+                Ok(inst)
+            }
+            None => Instruction::try_from(word),
+        }
+    }
+
+    fn invalidate(&mut self, addr: usize) {
+        if let Some(entry) = self.entries.get_mut(addr) {
+            *entry = None;
+        }
+    }
+}
+
+/// How `Add` and `Mul` treat results that do not fit in a `Word`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Arithmetic {
+    /// Fail with `Error::Overflow`.
+    #[default]
+    Checked,
+    /// Wrap around at the boundary of the type.
+    Wrapping,
+    /// Clamp to `Word::MIN` or `Word::MAX`.
+    Saturating,
+}
+
+impl Arithmetic {
+    /// Adds two values, or returns `None` on overflow under `Checked`.
+    pub fn add(self, a: Word, b: Word) -> Option<Word> {
+        match self {
+            Arithmetic::Checked => a.checked_add(b),
+            Arithmetic::Wrapping => Some(a.wrapping_add(b)),
+            Arithmetic::Saturating => Some(a.saturating_add(b)),
+        }
+    }
+
+    /// Multiplies two values, or returns `None` on overflow under `Checked`.
+    pub fn mul(self, a: Word, b: Word) -> Option<Word> {
+        match self {
+            Arithmetic::Checked => a.checked_mul(b),
+            Arithmetic::Wrapping => Some(a.wrapping_mul(b)),
+            Arithmetic::Saturating => Some(a.saturating_mul(b)),
+        }
+    }
+}
+
+/// The reason `Program::run_partial` returned control to the caller.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RunState {
+    /// The program executed an `Out` instruction.
+    Output(Word),
+    /// The program reached an `In` instruction and no input was available.
+    /// Calling `run_partial` again resumes at the same instruction.
+    NeedsInput,
+    /// The program executed a `Break` instruction.
+    Halted,
+}
+
 #[derive(Debug)]
-pub struct Program {
+pub struct Program<M = Dense> {
     pos: usize,
-    rel_base: i64,
+    rel_base: Word,
     done: bool,
-    code: Vec<i64>,
+    memory: M,
+    cache: DecodeCache,
+    arithmetic: Arithmetic,
 }
 
 impl Program {
-    pub fn new(code: &[i64]) -> Program {
+    pub fn new(code: &[Word]) -> Program {
+        Program::with_memory(code, Dense::new())
+    }
+}
+
+impl Program<Bounded> {
+    /// Creates a program that fails with `Error::MemoryLimit` instead of
+    /// growing its memory to `limit` cells or more.
+    pub fn with_memory_limit(code: &[Word], limit: usize) -> Program<Bounded> {
+        Program::with_memory(code, Bounded::new(limit))
+    }
+}
+
+impl<M: Memory> Program<M> {
+    /// Creates a program that stores its memory in the given backend.
+    pub fn with_memory(code: &[Word], memory: M) -> Program<M> {
+        let mut memory = memory;
+        memory.load(code);
         Program {
             pos: 0,
             rel_base: 0,
-            code: code.to_vec(),
             done: false,
+            memory,
+            cache: DecodeCache::new(code.len()),
+            arithmetic: Arithmetic::default(),
+        }
+    }
+
+    /// Creates a program that continues from a saved state, with its
+    /// memory in the given backend. Fails like `restore`.
+    pub fn from_snapshot(snapshot: &Snapshot, memory: M) -> Result<Program<M>, snapshot::Error> {
+        let mut program = Program::with_memory(&[], memory);
+        program.restore(snapshot)?;
+        Ok(program)
+    }
+
+    /// Captures the complete machine state.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            pc: self.pos,
+            rel_base: self.rel_base,
+            done: self.done,
+            len: self.memory.len(),
+            memory: self.memory.runs(),
         }
     }
 
+    /// Resets the machine to a saved state. The memory backend and its
+    /// configuration are kept. Fails with `snapshot::Error::Corrupt`, and
+    /// changes nothing, if the pc is past the memory or the memory runs
+    /// overlap or lie past its length. Fails with
+    /// `snapshot::Error::MemoryLimit` if the memory does not fit in the
+    /// backend, which leaves the memory partly loaded.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), snapshot::Error> {
+        snapshot.check()?;
+        self.memory
+            .load_runs(snapshot.len, &snapshot.memory)
+            .map_err(snapshot::Error::MemoryLimit)?;
+        self.pos = snapshot.pc;
+        self.rel_base = snapshot.rel_base;
+        self.done = snapshot.done;
+        // Cache the run at address 0, where the code is.
+        let code = match snapshot.memory.first() {
+            Some((0, cells)) => cells.len(),
+            _ => 0,
+        };
+        self.cache.reset(code);
+        Ok(())
+    }
+
+    /// Enables or disables caching of decoded instructions. The cache is
+    /// on by default; turning it off decodes every instruction word as it
+    /// is executed.
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.cache.enabled = enabled;
+    }
+
+    /// Sets how `Add` and `Mul` handle overflow. Defaults to
+    /// `Arithmetic::Checked`.
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+
+    pub fn arithmetic(&self) -> Arithmetic {
+        self.arithmetic
+    }
+
     pub fn is_done(&self) -> bool {
         self.done
     }
 
-    fn get_code(&self) -> &[i64] {
-        &self.code
+    /// Address of the next instruction to execute.
+    pub fn pc(&self) -> usize {
+        self.pos
+    }
+
+    pub fn rel_base(&self) -> Word {
+        self.rel_base
+    }
+
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    /// Reads the memory at `addr` without touching it.
+    pub fn peek(&self, addr: usize) -> Word {
+        self.memory.peek(addr)
+    }
+
+    fn fault(&self) -> Fault {
+        Fault {
+            pc: self.pos,
+            instruction: self.memory.peek(self.pos),
+            rel_base: self.rel_base,
+        }
+    }
+
+    fn limit_error(&self, e: LimitExceeded) -> Error {
+        Error::MemoryLimit {
+            addr: e.addr,
+            limit: e.limit,
+            fault: self.fault(),
+        }
+    }
+
+    fn load(&mut self, addr: usize) -> Result<Word, Error> {
+        self.memory.read(addr).map_err(|e| self.limit_error(e))
+    }
+
+    fn write(&mut self, addr: usize, value: Word) -> Result<MemWrite, Error> {
+        let old = self
+            .memory
+            .write(addr, value)
+            .map_err(|e| self.limit_error(e))?;
+        self.cache.invalidate(addr);
+        Ok(MemWrite {
+            addr,
+            old,
+            new: value,
+        })
+    }
+
+    fn get_param_value(&mut self, mode: ParamMode, pos: usize) -> Result<Word, Error> {
+        let addr = self.get_addr(mode, pos)?;
+        self.load(addr)
     }
 
-    fn get_param_value(&mut self, mode: ParamMode, pos: usize) -> i64 {
-        let addr = self.get_dest_addr(mode, pos);
-        self.code[addr]
+    fn get_dest_addr(&mut self, mode: ParamMode, pos: usize) -> Result<usize, Error> {
+        if let ParamMode::Immediate = mode {
+            return Err(Error::ImmediateWrite(self.fault()));
+        }
+        let addr = self.get_addr(mode, pos)?;
+        self.load(addr)?;
+        Ok(addr)
     }
 
-    fn get_dest_addr(&mut self, mode: ParamMode, pos: usize) -> usize {
+    fn get_addr(&mut self, mode: ParamMode, pos: usize) -> Result<usize, Error> {
         let addr = match mode {
-            ParamMode::Position => self.code[pos] as usize,
-            ParamMode::Immediate => pos,
-            ParamMode::Relative => (self.code[pos] + self.rel_base) as usize,
+            ParamMode::Position => self.load(pos)?,
+            ParamMode::Immediate => pos as Word,
+            ParamMode::Relative => {
+                let offset = self.load(pos)?;
+                offset
+                    .checked_add(self.rel_base)
+                    .ok_or_else(|| Error::Overflow(self.fault()))?
+            }
         };
-        if addr >= self.code.len() {
-            self.code.resize(addr + 1, 0);
+        if addr < 0 {
+            return Err(Error::NegativeAddress {
+                addr,
+                fault: self.fault(),
+            });
+        }
+        // Addresses past the address space fail in the memory backend like
+        // any other address that is too large to allocate.
+        Ok(usize::try_from(addr).unwrap_or(usize::MAX))
+    }
+
+    fn jump(&mut self, target: Word) -> Result<(), Error> {
+        match usize::try_from(target) {
+            Ok(pos) if pos < self.memory.len() => {
+                self.pos = pos;
+                Ok(())
+            }
+            _ => Err(Error::JumpOutOfRange {
+                target,
+                fault: self.fault(),
+            }),
         }
-        addr
     }
 
-    pub fn run_partial<'a>(
+    pub fn run_partial(
         &mut self,
-        input_iter: &mut dyn FnMut() -> i64,
-    ) -> Result<Option<i64>, Error> {
-        use Operator as Op;
+        input_iter: &mut dyn FnMut() -> Option<Word>,
+    ) -> Result<RunState, Error> {
+        self.run_partial_observed(input_iter, &mut trace::Quiet)
+    }
 
+    /// Like `run_partial`, but reports every executed instruction to
+    /// `observer`.
+    pub fn run_partial_observed(
+        &mut self,
+        input_iter: &mut dyn FnMut() -> Option<Word>,
+        observer: &mut dyn Observer,
+    ) -> Result<RunState, Error> {
         loop {
-            let inst = Instruction::try_from(self.code[self.pos])?;
-            match inst.op {
-                Op::Add | Op::Mul => {
-                    let op1 = self.get_param_value(inst.m1, self.pos + 1);
-                    let op2 = self.get_param_value(inst.m2, self.pos + 2);
-                    let dest = self.get_dest_addr(inst.m3, self.pos + 3);
-                    match inst.op {
-                        Op::Add => self.code[dest] = op1 + op2,
-                        Op::Mul => self.code[dest] = op1 * op2,
-                        _ => panic!("How did this happen?"),
-                    };
-                }
-                Op::In => {
-                    let dest = self.get_dest_addr(inst.m1, self.pos + 1);
-                    self.code[dest] = input_iter();
-                }
-                Op::Out => {
-                    let dest = self.get_dest_addr(inst.m1, self.pos + 1);
-                    self.pos += inst.increment();
-                    return Ok(Some(self.code[dest]));
-                }
-                Op::JumpIfTrue => {
-                    let op1 = self.get_param_value(inst.m1, self.pos + 1);
-                    let op2 = self.get_param_value(inst.m2, self.pos + 2);
-                    if op1 != 0 {
-                        self.pos = op2 as usize;
-                        continue;
-                    }
-                }
-                Op::JumpIfFalse => {
-                    let op1 = self.get_param_value(inst.m1, self.pos + 1);
-                    let op2 = self.get_param_value(inst.m2, self.pos + 2);
-                    if op1 == 0 {
-                        self.pos = op2 as usize;
-                        continue;
-                    }
-                }
-                Op::LessThan => {
-                    let op1 = self.get_param_value(inst.m1, self.pos + 1);
-                    let op2 = self.get_param_value(inst.m2, self.pos + 2);
-                    let dest = self.get_dest_addr(inst.m3, self.pos + 3);
-                    self.code[dest] = if op1 < op2 { 1 } else { 0 }
-                }
-                Op::Equals => {
-                    let op1 = self.get_param_value(inst.m1, self.pos + 1);
-                    let op2 = self.get_param_value(inst.m2, self.pos + 2);
-                    let dest = self.get_dest_addr(inst.m3, self.pos + 3);
-                    self.code[dest] = if op1 == op2 { 1 } else { 0 }
-                }
-                Op::Break => {
-                    break;
+            if let Some(state) = self.execute(input_iter, observer)? {
+                return Ok(state);
+            }
+        }
+    }
+
+    /// Executes the instruction at the program counter. Returns `None` if
+    /// the instruction neither produced output nor halted the program, and
+    /// `Some(RunState::NeedsInput)` without executing anything if the
+    /// instruction is an `In` and `input_iter` has no value.
+    pub fn step(
+        &mut self,
+        input_iter: &mut dyn FnMut() -> Option<Word>,
+    ) -> Result<Option<RunState>, Error> {
+        self.execute(input_iter, &mut trace::Quiet)
+    }
+
+    /// Like `step`, but reports the executed instruction to `observer`.
+    pub fn step_observed(
+        &mut self,
+        input_iter: &mut dyn FnMut() -> Option<Word>,
+        observer: &mut dyn Observer,
+    ) -> Result<Option<RunState>, Error> {
+        self.execute(input_iter, observer)
+    }
+
+    fn execute<O: Observer + ?Sized>(
+        &mut self,
+        input_iter: &mut dyn FnMut() -> Option<Word>,
+        observer: &mut O,
+    ) -> Result<Option<RunState>, Error> {
+        use Operator as Op;
+
+        if self.done {
+            return Ok(Some(RunState::Halted));
+        }
+
+        let word = self.memory.peek(self.pos);
+        let inst = self.cache.decode(self.pos, word)?;
+        let mut event = Event::new(self.pos, word, inst);
+        let mut next = (self.pos + inst.increment()) as Word;
+        let mut state = None;
+        match inst.op {
+            Op::Add | Op::Mul => {
+                let op1 = self.get_param_value(inst.m1, self.pos + 1)?;
+                let op2 = self.get_param_value(inst.m2, self.pos + 2)?;
+                let dest = self.get_dest_addr(inst.m3, self.pos + 3)?;
+                event.push_operand(op1);
+                event.push_operand(op2);
+                let value = match inst.op {
+                    Op::Add => self.arithmetic.add(op1, op2),
+                    Op::Mul => self.arithmetic.mul(op1, op2),
+                    _ => panic!("How did this happen?"),
+                };
+                let value = value.ok_or_else(|| Error::Overflow(self.fault()))?;
+                event.write = Some(self.write(dest, value)?);
+            }
+            Op::In => {
+                let dest = self.get_dest_addr(inst.m1, self.pos + 1)?;
+                let value = match input_iter() {
+                    Some(value) => value,
+                    None => return Ok(Some(RunState::NeedsInput)),
+                };
+                event.write = Some(self.write(dest, value)?);
+            }
+            Op::Out => {
+                let value = self.get_param_value(inst.m1, self.pos + 1)?;
+                event.push_operand(value);
+                state = Some(RunState::Output(value));
+            }
+            Op::JumpIfTrue => {
+                let op1 = self.get_param_value(inst.m1, self.pos + 1)?;
+                let op2 = self.get_param_value(inst.m2, self.pos + 2)?;
+                event.push_operand(op1);
+                event.push_operand(op2);
+                if op1 != 0 {
+                    next = op2;
                 }
-                Op::AdjRelBase => {
-                    self.rel_base += self.get_param_value(inst.m1, self.pos + 1);
+            }
+            Op::JumpIfFalse => {
+                let op1 = self.get_param_value(inst.m1, self.pos + 1)?;
+                let op2 = self.get_param_value(inst.m2, self.pos + 2)?;
+                event.push_operand(op1);
+                event.push_operand(op2);
+                if op1 == 0 {
+                    next = op2;
                 }
-            };
-            self.pos += inst.increment();
+            }
+            Op::LessThan => {
+                let op1 = self.get_param_value(inst.m1, self.pos + 1)?;
+                let op2 = self.get_param_value(inst.m2, self.pos + 2)?;
+                let dest = self.get_dest_addr(inst.m3, self.pos + 3)?;
+                event.push_operand(op1);
+                event.push_operand(op2);
+                event.write = Some(self.write(dest, if op1 < op2 { 1 } else { 0 })?);
+            }
+            Op::Equals => {
+                let op1 = self.get_param_value(inst.m1, self.pos + 1)?;
+                let op2 = self.get_param_value(inst.m2, self.pos + 2)?;
+                let dest = self.get_dest_addr(inst.m3, self.pos + 3)?;
+                event.push_operand(op1);
+                event.push_operand(op2);
+                event.write = Some(self.write(dest, if op1 == op2 { 1 } else { 0 })?);
+            }
+            Op::Break => {
+                self.done = true;
+                observer.on_instruction(&event);
+                return Ok(Some(RunState::Halted));
+            }
+            Op::AdjRelBase => {
+                let offset = self.get_param_value(inst.m1, self.pos + 1)?;
+                event.push_operand(offset);
+                let rel_base = self
+                    .rel_base
+                    .checked_add(offset)
+                    .ok_or_else(|| Error::Overflow(self.fault()))?;
+                event.rel_base = Some((self.rel_base, rel_base));
+                self.rel_base = rel_base;
+            }
+        };
+        self.jump(next)?;
+        observer.on_instruction(&event);
+        Ok(state)
+    }
+
+    /// Runs the program until it halts, passing every output value to
+    /// `output`. Fails with `Error::InputExhausted` if the program asks for
+    /// input that `input` cannot provide.
+    fn run_to_end(
+        &mut self,
+        input: &mut dyn FnMut() -> Option<Word>,
+        output: &mut dyn FnMut(Word),
+    ) -> Result<(), Error> {
+        loop {
+            match self.run_partial(input)? {
+                RunState::Output(res) => output(res),
+                RunState::NeedsInput => return Err(Error::InputExhausted(self.fault())),
+                RunState::Halted => return Ok(()),
+            }
         }
-        self.done = true;
-        Ok(None)
     }
 }
 
 pub trait Intcode {
-    fn run(&mut self, input: &[i64], output: &mut dyn FnMut(i64) -> ()) -> Result<(), Error>;
+    fn run(&mut self, input: &[Word], output: &mut dyn FnMut(Word)) -> Result<(), Error>;
     fn run_iter(
         &mut self,
-        input: &mut dyn FnMut() -> i64,
-        output: &mut dyn FnMut(i64) -> (),
+        input: &mut dyn FnMut() -> Word,
+        output: &mut dyn FnMut(Word),
     ) -> Result<(), Error>;
 }
 
-impl Intcode for &mut [i64] {
-    fn run(&mut self, input: &[i64], output: &mut dyn FnMut(i64) -> ()) -> Result<(), Error> {
+impl Intcode for &mut [Word] {
+    fn run(&mut self, input: &[Word], output: &mut dyn FnMut(Word)) -> Result<(), Error> {
         let mut input_iter = input.iter();
-        let mut func = move || *input_iter.next().unwrap();
+        let mut func = move || input_iter.next().copied();
         let mut prog = Program::new(self);
-        while !prog.is_done() {
-            if let Some(res) = prog.run_partial(&mut func)? {
-                output(res);
-            }
-        }
+        prog.run_to_end(&mut func, output)?;
         let l = self.len();
-        self[..].clone_from_slice(&prog.get_code()[..l]);
+        self[..].clone_from_slice(&prog.memory.as_slice()[..l]);
         Ok(())
     }
 
     fn run_iter(
         &mut self,
-        input: &mut dyn FnMut() -> i64,
-        output: &mut dyn FnMut(i64) -> (),
+        input: &mut dyn FnMut() -> Word,
+        output: &mut dyn FnMut(Word),
     ) -> Result<(), Error> {
         let mut prog = Program::new(self);
-        while !prog.is_done() {
-            if let Some(res) = prog.run_partial(input)? {
-                output(res);
-            }
-        }
+        prog.run_to_end(&mut || Some(input()), output)?;
         let l = self.len();
-        self[..].clone_from_slice(&prog.get_code()[..l]);
+        self[..].clone_from_slice(&prog.memory.as_slice()[..l]);
         Ok(())
     }
 }
 
-impl Intcode for Vec<i64> {
-    fn run(&mut self, input: &[i64], output: &mut dyn FnMut(i64) -> ()) -> Result<(), Error> {
+impl Intcode for Vec<Word> {
+    fn run(&mut self, input: &[Word], output: &mut dyn FnMut(Word)) -> Result<(), Error> {
         let mut input_iter = input.iter();
-        let mut func = move || *input_iter.next().unwrap();
+        let mut func = move || input_iter.next().copied();
         let mut prog = Program::new(self);
-        while !prog.is_done() {
-            if let Some(res) = prog.run_partial(&mut func)? {
-                output(res);
-            }
-        }
-        self.clear();
-        self.extend(prog.get_code().iter());
+        prog.run_to_end(&mut func, output)?;
+        *self = prog.memory.to_vec();
         Ok(())
     }
 
     fn run_iter(
         &mut self,
-        input: &mut dyn FnMut() -> i64,
-        output: &mut dyn FnMut(i64) -> (),
+        input: &mut dyn FnMut() -> Word,
+        output: &mut dyn FnMut(Word),
     ) -> Result<(), Error> {
         let mut prog = Program::new(self);
-        while !prog.is_done() {
-            if let Some(res) = prog.run_partial(input)? {
-                output(res);
-            }
-        }
-        self.clear();
-        self.extend(prog.get_code().iter());
+        prog.run_to_end(&mut || Some(input()), output)?;
+        *self = prog.memory.to_vec();
         Ok(())
     }
 }
@@ -512,4 +1000,292 @@ mod test {
         code.run(&input, &mut |r| output.push(r)).unwrap();
         assert_eq!(output[0], 1125899906842624);
     }
+
+    #[test]
+    fn test_needs_input() {
+        let code = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        let mut prog = Program::new(&code);
+        assert_eq!(
+            prog.run_partial(&mut || None).unwrap(),
+            RunState::NeedsInput
+        );
+        assert_eq!(
+            prog.run_partial(&mut || None).unwrap(),
+            RunState::NeedsInput
+        );
+        assert_eq!(
+            prog.run_partial(&mut || Some(8)).unwrap(),
+            RunState::Output(1)
+        );
+        assert_eq!(prog.run_partial(&mut || None).unwrap(), RunState::Halted);
+        assert!(prog.is_done());
+        assert_eq!(prog.run_partial(&mut || None).unwrap(), RunState::Halted);
+    }
+
+    #[test]
+    fn test_input_exhausted() {
+        let mut code = vec![3, 0, 3, 0, 99];
+        let input = [1];
+        match code.run(&input, &mut |_| ()) {
+            Err(Error::InputExhausted(fault)) => {
+                assert_eq!(fault.pc, 2);
+                assert_eq!(fault.instruction, 3);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    fn run_err(code: &[Word]) -> Error {
+        let mut prog = Program::new(code);
+        match prog.run_partial(&mut || Some(0)) {
+            Err(e) => e,
+            Ok(state) => panic!("expected an error, got {:?}", state),
+        }
+    }
+
+    #[test]
+    fn test_negative_address() {
+        match run_err(&[109, -5, 22201, 0, 0, 0, 99]) {
+            Error::NegativeAddress { addr, fault } => {
+                assert_eq!(addr, -5);
+                assert_eq!(
+                    fault,
+                    Fault {
+                        pc: 2,
+                        instruction: 22201,
+                        rel_base: -5
+                    }
+                );
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_immediate_write() {
+        match run_err(&[11101, 1, 1, 0, 99]) {
+            Error::ImmediateWrite(fault) => assert_eq!(fault.instruction, 11101),
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_jump_out_of_range() {
+        match run_err(&[1105, 1, -1]) {
+            Error::JumpOutOfRange { target, fault } => {
+                assert_eq!(target, -1);
+                assert_eq!(fault.pc, 0);
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+        match run_err(&[1101, 1, 1, 0]) {
+            Error::JumpOutOfRange { target, .. } => assert_eq!(target, 4),
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let mut prog = Program::with_memory_limit(&[1101, 1, 1, 1 << 40, 99], 1024);
+        match prog.run_partial(&mut || None) {
+            Err(Error::MemoryLimit { addr, limit, fault }) => {
+                assert_eq!(addr, 1 << 40);
+                assert_eq!(limit, 1024);
+                assert_eq!(fault.pc, 0);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let mut prog = Program::with_memory_limit(&[1101, 1, 1, 1000, 4, 1000, 99], 1024);
+        assert_eq!(prog.run_partial(&mut || None).unwrap(), RunState::Output(2));
+    }
+
+    fn run_with<M: Memory>(memory: M, code: &[Word], input: &[Word]) -> (Vec<Word>, Vec<Word>) {
+        let mut prog = Program::with_memory(code, memory);
+        let mut input = input.iter().copied();
+        let mut output = Vec::new();
+        prog.run_to_end(&mut || input.next(), &mut |r| output.push(r))
+            .unwrap();
+        (output, prog.memory().to_vec())
+    }
+
+    #[test]
+    fn test_backends() {
+        let quine = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        let eq_8_pos = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        let lt_8_pos = vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+        let eq_8_imm = vec![3, 3, 1108, -1, 8, 3, 4, 3, 99];
+        let lt_8_imm = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
+        let jump_pos = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+        let jump_imm = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+        // (program, input, expected output, expected start of memory)
+        let cases = vec![
+            (vec![1, 0, 0, 0, 99], vec![], vec![], vec![2, 0, 0, 0, 99]),
+            (vec![2, 3, 0, 3, 99], vec![], vec![], vec![2, 3, 0, 6, 99]),
+            (
+                vec![2, 4, 4, 5, 99, 0],
+                vec![],
+                vec![],
+                vec![2, 4, 4, 5, 99, 9801],
+            ),
+            (
+                vec![1, 1, 1, 4, 99, 5, 6, 0, 99],
+                vec![],
+                vec![],
+                vec![30, 1, 1, 4, 2, 5, 6, 0, 99],
+            ),
+            (vec![3, 0, 4, 0, 99], vec![11], vec![11], vec![11]),
+            (eq_8_pos.clone(), vec![8], vec![1], vec![]),
+            (eq_8_pos, vec![7], vec![0], vec![]),
+            (lt_8_pos.clone(), vec![7], vec![1], vec![]),
+            (lt_8_pos, vec![8], vec![0], vec![]),
+            (eq_8_imm.clone(), vec![8], vec![1], vec![]),
+            (eq_8_imm, vec![7], vec![0], vec![]),
+            (lt_8_imm.clone(), vec![7], vec![1], vec![]),
+            (lt_8_imm, vec![8], vec![0], vec![]),
+            (jump_pos.clone(), vec![0], vec![0], vec![]),
+            (jump_pos, vec![1], vec![1], vec![]),
+            (jump_imm.clone(), vec![0], vec![0], vec![]),
+            (jump_imm, vec![1], vec![1], vec![]),
+            (quine.clone(), vec![], quine, vec![]),
+            (
+                vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0],
+                vec![],
+                vec![1219070632396864],
+                vec![],
+            ),
+            (
+                vec![104, 1125899906842624, 99],
+                vec![],
+                vec![1125899906842624],
+                vec![],
+            ),
+        ];
+        for (code, input, output, memory) in cases {
+            let results = vec![
+                run_with(Dense::new(), &code, &input),
+                run_with(memory::Paged::new(), &code, &input),
+                run_with(Bounded::new(4096), &code, &input),
+            ];
+            for (out, mem) in results {
+                assert_eq!(out, output);
+                assert_eq!(&mem[..memory.len()], &memory[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_paged_high_address() {
+        let code = [1101, 1, 1, 1 << 40, 4, 1 << 40, 99];
+        let mut prog = Program::with_memory(&code, memory::Paged::new());
+        assert_eq!(prog.run_partial(&mut || None).unwrap(), RunState::Output(2));
+        assert_eq!(prog.memory().num_pages(), 2);
+    }
+
+    #[test]
+    fn test_decode() {
+        let modes = [
+            ParamMode::Position,
+            ParamMode::Immediate,
+            ParamMode::Relative,
+        ];
+        for code in [1, 2, 3, 4, 5, 6, 7, 8, 9, 99].iter() {
+            let op = Operator::try_from(*code).unwrap();
+            for &m1 in modes.iter() {
+                for &m2 in modes.iter() {
+                    for &m3 in modes.iter() {
+                        let inst = Instruction { op, m1, m2, m3 };
+                        let word = ((m3.code() * 10 + m2.code()) * 10 + m1.code()) * 100 + code;
+                        assert_eq!(Instruction::try_from(word).unwrap(), inst);
+                    }
+                }
+            }
+        }
+        assert!(matches!(
+            Instruction::try_from(-1),
+            Err(Error::UnknownOpcode(-1))
+        ));
+        assert!(matches!(
+            Instruction::try_from(301),
+            Err(Error::UnknownMode(3))
+        ));
+        assert!(matches!(
+            Instruction::try_from(100001),
+            Err(Error::UnknownMode(10))
+        ));
+    }
+
+    #[test]
+    fn test_self_modifying() {
+        // Outputs 5, overwrites its first instruction with a halt and
+        // jumps back to it.
+        let code = [104, 5, 1101, 0, 99, 0, 1105, 1, 0];
+        for &cached in [true, false].iter() {
+            let mut prog = Program::new(&code);
+            prog.set_decode_cache(cached);
+            let mut output = Vec::new();
+            prog.run_to_end(&mut || None, &mut |r| output.push(r))
+                .unwrap();
+            assert_eq!(output, vec![5]);
+        }
+
+        let mut prog = Program::new(&code);
+        prog.run_partial(&mut || None).unwrap();
+        let snapshot = prog.snapshot();
+        assert_eq!(prog.run_partial(&mut || None).unwrap(), RunState::Halted);
+        prog.restore(&Program::new(&code).snapshot()).unwrap();
+        assert_eq!(prog.run_partial(&mut || None).unwrap(), RunState::Output(5));
+        prog.restore(&snapshot).unwrap();
+        assert_eq!(prog.run_partial(&mut || None).unwrap(), RunState::Halted);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let code = [1102, Word::MAX / 2, 3, 0, 4, 0, 99];
+        let mut prog = Program::new(&code);
+        match prog.run_partial(&mut || None) {
+            Err(Error::Overflow(fault)) => assert_eq!(fault.pc, 0),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        prog = Program::new(&code);
+        prog.set_arithmetic(Arithmetic::Wrapping);
+        let expected = (Word::MAX / 2).wrapping_mul(3);
+        assert_eq!(
+            prog.run_partial(&mut || None).unwrap(),
+            RunState::Output(expected)
+        );
+
+        prog = Program::new(&code);
+        prog.set_arithmetic(Arithmetic::Saturating);
+        assert_eq!(
+            prog.run_partial(&mut || None).unwrap(),
+            RunState::Output(Word::MAX)
+        );
+
+        let code = [1101, Word::MIN, -1, 0, 4, 0, 99];
+        prog = Program::new(&code);
+        prog.set_arithmetic(Arithmetic::Saturating);
+        assert_eq!(
+            prog.run_partial(&mut || None).unwrap(),
+            RunState::Output(Word::MIN)
+        );
+    }
+
+    #[test]
+    fn test_relative_overflow() {
+        let code = [109, Word::MAX, 109, 1, 99];
+        let mut prog = Program::new(&code);
+        prog.set_arithmetic(Arithmetic::Wrapping);
+        match prog.run_partial(&mut || None) {
+            Err(Error::Overflow(fault)) => assert_eq!(fault.pc, 2),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match run_err(&[109, Word::MAX, 204, 1, 99]) {
+            Error::Overflow(fault) => assert_eq!(fault.pc, 2),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,161 @@
+//! Reading programs from text.
+//!
+//! A program is a comma-separated list of integers. Whitespace and newlines
+//! may appear anywhere between values, a trailing comma is allowed, and
+//! `;` starts a comment that runs to the end of the line:
+//!
+//! ```text
+//! ; print 42 and stop
+//! 104, 42,   ; out #42
+//! 99,        ; hlt
+//! ```
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::Word;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A token that is not an integer, or that does not fit in a `Word`.
+    InvalidValue {
+        offset: usize,
+        token: String,
+    },
+    /// A comma with no value before it.
+    MissingValue {
+        offset: usize,
+    },
+    /// Two values with no comma between them.
+    MissingComma {
+        offset: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::InvalidValue { offset, token } => {
+                write!(f, "Invalid value {:?} at byte {}", token, offset)
+            }
+            Error::MissingValue { offset } => write!(f, "Missing value at byte {}", offset),
+            Error::MissingComma { offset } => write!(f, "Missing comma at byte {}", offset),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+/// Parses a program. Byte offsets in errors are relative to `text`.
+pub fn from_str(text: &str) -> Result<Vec<Word>, Error> {
+    let mut code = Vec::new();
+    // Whether the last item was a value, which must be followed by a comma.
+    let mut after_value = false;
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        if c == ';' {
+            while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+        } else if c == ',' {
+            if !after_value {
+                return Err(Error::MissingValue { offset });
+            }
+            after_value = false;
+        } else if !c.is_whitespace() {
+            if after_value {
+                return Err(Error::MissingComma { offset });
+            }
+            let mut end = offset + c.len_utf8();
+            while let Some((i, c)) = chars.next_if(|&(_, c)| !is_separator(c)) {
+                end = i + c.len_utf8();
+            }
+            let token = &text[offset..end];
+            let value = token.parse().map_err(|_| Error::InvalidValue {
+                offset,
+                token: token.to_string(),
+            })?;
+            code.push(value);
+            after_value = true;
+        }
+    }
+    Ok(code)
+}
+
+fn is_separator(c: char) -> bool {
+    c == ',' || c == ';' || c.is_whitespace()
+}
+
+/// Reads and parses a program.
+pub fn from_reader(reader: &mut dyn Read) -> Result<Vec<Word>, Error> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    from_str(&text)
+}
+
+/// Reads and parses a program file.
+pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Vec<Word>, Error> {
+    from_reader(&mut File::open(path)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(from_str("1,0,0,0,99\n").unwrap(), vec![1, 0, 0, 0, 99]);
+        assert_eq!(from_str(" 1 , -2,\n 3 ,\n").unwrap(), vec![1, -2, 3]);
+        assert_eq!(from_str("").unwrap(), Vec::<Word>::new());
+        assert_eq!(
+            from_str("; header\n104, 42, ; out\n\n99 ; hlt").unwrap(),
+            vec![104, 42, 99]
+        );
+        assert_eq!(from_str("1,\n2\n,3").unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_errors() {
+        match from_str("1,2,x3,4") {
+            Err(Error::InvalidValue { offset, token }) => {
+                assert_eq!(offset, 4);
+                assert_eq!(token, "x3");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match from_str("1, 2,\n 99999999999999999999999999999999999999999") {
+            Err(Error::InvalidValue { offset, .. }) => assert_eq!(offset, 7),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match from_str("1,2,,3") {
+            Err(Error::MissingValue { offset }) => assert_eq!(offset, 4),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match from_str(" ,1") {
+            Err(Error::MissingValue { offset }) => assert_eq!(offset, 1),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match from_str("1,\n2 3") {
+            Err(Error::MissingComma { offset }) => assert_eq!(offset, 5),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reader() {
+        let code = from_reader(&mut "3,0,4,0,99".as_bytes()).unwrap();
+        assert_eq!(code, vec![3, 0, 4, 0, 99]);
+        assert!(matches!(
+            from_path("/nonexistent/program"),
+            Err(Error::Io(_))
+        ));
+    }
+}
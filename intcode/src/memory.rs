@@ -0,0 +1,448 @@
+//! Memory backends for `Program`.
+//!
+//! Intcode memory is conceptually infinite and zero-initialized. Every
+//! backend tracks the highest address the program has touched, which
+//! `Program` uses to decide whether a jump target is valid.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Word;
+
+/// Number of cells in a page of `Paged` memory.
+pub const PAGE_SIZE: usize = 4096;
+
+/// An access past the end of a `Bounded` memory, or past what a backend
+/// can address at all.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LimitExceeded {
+    pub addr: usize,
+    pub limit: usize,
+}
+
+/// The cell one past `addr`, which is where a memory that touches `addr`
+/// ends.
+fn end_of(addr: usize) -> Result<usize, LimitExceeded> {
+    addr.checked_add(1).ok_or(LimitExceeded {
+        addr,
+        limit: usize::MAX,
+    })
+}
+
+pub trait Memory {
+    /// Replaces the contents of the memory with `code`.
+    fn load(&mut self, code: &[Word]);
+
+    /// Reads a cell. Cells that were never written read as zero.
+    fn read(&mut self, addr: usize) -> Result<Word, LimitExceeded>;
+
+    /// Writes a cell and returns its previous value.
+    fn write(&mut self, addr: usize, value: Word) -> Result<Word, LimitExceeded>;
+
+    /// Reads a cell without marking it as touched.
+    fn peek(&self, addr: usize) -> Word;
+
+    /// One past the highest address that was loaded, read or written.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copies the cells below `len()`.
+    fn to_vec(&self) -> Vec<Word> {
+        (0..self.len()).map(|addr| self.peek(addr)).collect()
+    }
+
+    /// The cells below `len()` as runs of consecutive cells, each with the
+    /// address of its first cell. Cells outside every run are zero.
+    fn runs(&self) -> Vec<(usize, Vec<Word>)> {
+        vec![(0, self.to_vec())]
+    }
+
+    /// Replaces the contents of the memory with `runs`, as returned by
+    /// `runs`, and makes `len()` return `len`. Fails if a run or the
+    /// length does not fit in the memory, which may then be left partly
+    /// loaded.
+    fn load_runs(&mut self, len: usize, runs: &[(usize, Vec<Word>)]) -> Result<(), LimitExceeded> {
+        self.load(&[]);
+        for (addr, values) in runs {
+            for (i, &value) in values.iter().enumerate() {
+                if value != 0 {
+                    self.write(addr + i, value)?;
+                }
+            }
+        }
+        if len > self.len() {
+            self.read(len - 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Cells past what a `Vec<Word>` can hold, which `Dense` memory fails to
+/// grow to.
+const DENSE_LIMIT: usize = isize::MAX as usize / std::mem::size_of::<Word>();
+
+/// A contiguous vector that grows to cover every written address. Reads
+/// past the end only move `len()`.
+#[derive(Clone, Debug, Default)]
+pub struct Dense {
+    cells: Vec<Word>,
+    len: usize,
+}
+
+impl Dense {
+    pub fn new() -> Dense {
+        Dense::default()
+    }
+
+    pub(crate) fn as_slice(&self) -> &[Word] {
+        &self.cells
+    }
+
+    /// Allocates the cells below `end`, failing instead of aborting if
+    /// there are too many.
+    fn grow(&mut self, end: usize) -> Result<(), LimitExceeded> {
+        if end > self.cells.len() {
+            self.cells
+                .try_reserve_exact(end - self.cells.len())
+                .map_err(|_| LimitExceeded {
+                    addr: end - 1,
+                    limit: DENSE_LIMIT,
+                })?;
+            self.cells.resize(end, 0);
+        }
+        Ok(())
+    }
+}
+
+impl Memory for Dense {
+    fn load(&mut self, code: &[Word]) {
+        self.cells = code.to_vec();
+        self.len = code.len();
+    }
+
+    fn read(&mut self, addr: usize) -> Result<Word, LimitExceeded> {
+        self.len = self.len.max(end_of(addr)?);
+        Ok(self.peek(addr))
+    }
+
+    fn write(&mut self, addr: usize, value: Word) -> Result<Word, LimitExceeded> {
+        let end = end_of(addr)?;
+        self.grow(end)?;
+        self.len = self.len.max(end);
+        Ok(std::mem::replace(&mut self.cells[addr], value))
+    }
+
+    fn peek(&self, addr: usize) -> Word {
+        self.cells.get(addr).copied().unwrap_or(0)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn to_vec(&self) -> Vec<Word> {
+        let mut cells = self.cells.clone();
+        cells.resize(self.len, 0);
+        cells
+    }
+
+    /// Only the allocated cells, which leaves out the zeros that were
+    /// read but never written.
+    fn runs(&self) -> Vec<(usize, Vec<Word>)> {
+        vec![(0, self.cells.clone())]
+    }
+
+    /// Allocates the cells up to the end of the last run, but not up to
+    /// `len`.
+    fn load_runs(&mut self, len: usize, runs: &[(usize, Vec<Word>)]) -> Result<(), LimitExceeded> {
+        self.load(&[]);
+        for (addr, values) in runs {
+            if !values.is_empty() {
+                let end = addr + values.len();
+                self.grow(end)?;
+                self.cells[*addr..end].copy_from_slice(values);
+            }
+        }
+        self.len = len.max(self.cells.len());
+        Ok(())
+    }
+}
+
+/// Sparse memory that allocates `PAGE_SIZE` pages on the first write.
+#[derive(Clone, Default)]
+pub struct Paged {
+    pages: HashMap<usize, Box<[Word; PAGE_SIZE]>>,
+    len: usize,
+}
+
+impl Paged {
+    pub fn new() -> Paged {
+        Paged::default()
+    }
+
+    /// Number of allocated pages.
+    pub fn num_pages(&self) -> usize {
+        self.pages.len()
+    }
+}
+
+impl fmt::Debug for Paged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Paged")
+            .field("pages", &self.pages.len())
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl Memory for Paged {
+    fn load(&mut self, code: &[Word]) {
+        self.pages.clear();
+        self.len = 0;
+        for (addr, &value) in code.iter().enumerate() {
+            if value != 0 {
+                self.write(addr, value).unwrap();
+            }
+        }
+        self.len = code.len();
+    }
+
+    fn read(&mut self, addr: usize) -> Result<Word, LimitExceeded> {
+        self.len = self.len.max(end_of(addr)?);
+        Ok(self.peek(addr))
+    }
+
+    fn write(&mut self, addr: usize, value: Word) -> Result<Word, LimitExceeded> {
+        self.len = self.len.max(end_of(addr)?);
+        let page = self
+            .pages
+            .entry(addr / PAGE_SIZE)
+            .or_insert_with(|| Box::new([0; PAGE_SIZE]));
+        Ok(std::mem::replace(&mut page[addr % PAGE_SIZE], value))
+    }
+
+    fn peek(&self, addr: usize) -> Word {
+        self.pages
+            .get(&(addr / PAGE_SIZE))
+            .map(|page| page[addr % PAGE_SIZE])
+            .unwrap_or(0)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn to_vec(&self) -> Vec<Word> {
+        let mut cells = vec![0; self.len];
+        for (idx, page) in self.pages.iter() {
+            let start = idx * PAGE_SIZE;
+            let end = (start + PAGE_SIZE).min(self.len);
+            cells[start..end].copy_from_slice(&page[..end - start]);
+        }
+        cells
+    }
+
+    /// One run per stretch of allocated pages, so the runs stay as small
+    /// as the memory.
+    fn runs(&self) -> Vec<(usize, Vec<Word>)> {
+        let mut indices: Vec<_> = self.pages.keys().copied().collect();
+        indices.sort_unstable();
+        let mut runs: Vec<(usize, Vec<Word>)> = Vec::new();
+        for idx in indices {
+            let start = idx * PAGE_SIZE;
+            let end = (start + PAGE_SIZE).min(self.len);
+            let cells = &self.pages[&idx][..end - start];
+            match runs.last_mut() {
+                Some((addr, values)) if *addr + values.len() == start => {
+                    values.extend_from_slice(cells)
+                }
+                _ => runs.push((start, cells.to_vec())),
+            }
+        }
+        runs
+    }
+}
+
+/// Dense memory that refuses to grow to `limit` cells or more.
+#[derive(Clone, Debug)]
+pub struct Bounded {
+    inner: Dense,
+    limit: usize,
+}
+
+impl Bounded {
+    pub fn new(limit: usize) -> Bounded {
+        Bounded {
+            inner: Dense::new(),
+            limit,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    fn check(&self, addr: usize) -> Result<(), LimitExceeded> {
+        if addr >= self.inner.len() && addr >= self.limit {
+            return Err(LimitExceeded {
+                addr,
+                limit: self.limit,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Memory for Bounded {
+    fn load(&mut self, code: &[Word]) {
+        self.inner.load(code);
+    }
+
+    fn read(&mut self, addr: usize) -> Result<Word, LimitExceeded> {
+        self.check(addr)?;
+        self.inner.read(addr)
+    }
+
+    fn write(&mut self, addr: usize, value: Word) -> Result<Word, LimitExceeded> {
+        self.check(addr)?;
+        self.inner.write(addr, value)
+    }
+
+    fn peek(&self, addr: usize) -> Word {
+        self.inner.peek(addr)
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn to_vec(&self) -> Vec<Word> {
+        self.inner.to_vec()
+    }
+
+    fn runs(&self) -> Vec<(usize, Vec<Word>)> {
+        self.inner.runs()
+    }
+
+    /// Accepts runs past the limit, as `load` accepts code past it, but
+    /// not a length past both.
+    fn load_runs(&mut self, len: usize, runs: &[(usize, Vec<Word>)]) -> Result<(), LimitExceeded> {
+        let end = runs.last().map_or(0, |(addr, values)| addr + values.len());
+        if len > self.limit && len > end {
+            return Err(LimitExceeded {
+                addr: len - 1,
+                limit: self.limit,
+            });
+        }
+        self.inner.load_runs(len, runs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dense() {
+        let mut memory = Dense::new();
+        memory.load(&[1, 2, 3]);
+        assert_eq!(memory.peek(10), 0);
+        assert_eq!(memory.len(), 3);
+        assert_eq!(memory.read(5), Ok(0));
+        assert_eq!(memory.len(), 6);
+        assert_eq!(memory.write(1, 7), Ok(2));
+        assert_eq!(memory.to_vec(), vec![1, 7, 3, 0, 0, 0]);
+        assert_eq!(memory.runs(), vec![(0, vec![1, 7, 3])]);
+
+        let past_end = LimitExceeded {
+            addr: usize::MAX,
+            limit: usize::MAX,
+        };
+        assert_eq!(memory.read(usize::MAX), Err(past_end));
+        assert_eq!(memory.write(usize::MAX, 1), Err(past_end));
+        assert_eq!(
+            memory.write(1 << 62, 1),
+            Err(LimitExceeded {
+                addr: 1 << 62,
+                limit: DENSE_LIMIT
+            })
+        );
+    }
+
+    #[test]
+    fn test_paged() {
+        let mut memory = Paged::new();
+        memory.load(&[1, 0, 3]);
+        assert_eq!(memory.num_pages(), 1);
+        assert_eq!(memory.read(1 << 40), Ok(0));
+        assert_eq!(memory.num_pages(), 1);
+        assert_eq!(memory.len(), (1 << 40) + 1);
+        assert_eq!(memory.write(1 << 40, 5), Ok(0));
+        assert_eq!(memory.write(1 << 40, 6), Ok(5));
+        assert_eq!(memory.num_pages(), 2);
+        assert_eq!(memory.peek(1 << 40), 6);
+
+        let mut memory = Paged::new();
+        memory.load(&[1, 2]);
+        memory.write(PAGE_SIZE + 1, 9).unwrap();
+        let cells = memory.to_vec();
+        assert_eq!(cells.len(), PAGE_SIZE + 2);
+        assert_eq!(&cells[..2], &[1, 2]);
+        assert_eq!(cells[PAGE_SIZE + 1], 9);
+
+        let past_end = LimitExceeded {
+            addr: usize::MAX,
+            limit: usize::MAX,
+        };
+        assert_eq!(memory.read(usize::MAX), Err(past_end));
+        assert_eq!(memory.write(usize::MAX, 1), Err(past_end));
+    }
+
+    #[test]
+    fn test_runs() {
+        let mut memory = Paged::new();
+        memory.load(&[1, 2]);
+        memory.write(PAGE_SIZE + 1, 9).unwrap();
+        memory.write(1 << 40, 5).unwrap();
+        let runs = memory.runs();
+        assert_eq!(runs.len(), 2);
+        assert_eq!((runs[0].0, runs[0].1.len()), (0, PAGE_SIZE * 2));
+        assert_eq!((runs[1].0, runs[1].1.len()), (1 << 40, 1));
+
+        let mut copy = Paged::new();
+        copy.load_runs(memory.len(), &runs).unwrap();
+        assert_eq!((copy.len(), copy.num_pages()), (memory.len(), 3));
+        assert_eq!(copy.runs(), runs);
+
+        let mut dense = Dense::new();
+        dense.load_runs(1 << 40, &runs[..1]).unwrap();
+        assert_eq!(dense.len(), 1 << 40);
+        assert_eq!(dense.peek(PAGE_SIZE + 1), 9);
+        assert_eq!(dense.runs(), &runs[..1]);
+
+        let mut bounded = Bounded::new(PAGE_SIZE);
+        assert!(bounded.load_runs(PAGE_SIZE * 2, &runs[..1]).is_ok());
+        assert_eq!(
+            bounded.load_runs(PAGE_SIZE * 2 + 1, &runs[..1]),
+            Err(LimitExceeded {
+                addr: PAGE_SIZE * 2,
+                limit: PAGE_SIZE
+            })
+        );
+    }
+
+    #[test]
+    fn test_bounded() {
+        let mut memory = Bounded::new(4);
+        memory.load(&[1, 2, 3, 4, 5, 6]);
+        assert_eq!(memory.read(5), Ok(6));
+        assert_eq!(memory.read(6), Err(LimitExceeded { addr: 6, limit: 4 }));
+        let mut memory = Bounded::new(4);
+        memory.load(&[1]);
+        assert_eq!(memory.write(3, 1), Ok(0));
+        assert_eq!(memory.write(4, 1), Err(LimitExceeded { addr: 4, limit: 4 }));
+    }
+}
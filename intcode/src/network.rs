@@ -0,0 +1,626 @@
+//! Several programs connected by input queues.
+//!
+//! Every machine in a `Network` owns a queue of pending input values. Its
+//! `Route` decides where its output values go: into its own output buffer,
+//! into the queues of other machines (pipelines, rings and fan-out), or, for
+//! addressable networks, into the queue of the machine a packet is addressed
+//! to.
+//!
+//! Machines are scheduled round-robin in the order they were added. A turn
+//! lasts until the machine outputs a value, needs input that is not queued
+//! or halts, so a given network always runs the same way.
+//!
+//! By default a machine that reads from an empty queue blocks until another
+//! machine sends it a value. With `set_idle_input` it instead reads the
+//! given value once per turn, which is how packet networks poll for
+//! packets. A network where every machine polls an empty queue for a whole
+//! round without sending anything is idle; if a NAT has been configured
+//! with `set_nat` it then forwards the last packet it received to machine 0.
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::memory::{Dense, Memory};
+use crate::{Program, RunState, Word};
+
+/// Index of a machine in a `Network`. In packet networks it is also the
+/// machine's address.
+pub type MachineId = usize;
+
+/// Where a machine's output values go.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Route {
+    /// Kept in the machine's output buffer, see `Network::take_output`.
+    Sink,
+    /// Appended to the input queue of each listed machine.
+    Direct(Vec<MachineId>),
+    /// Grouped into `Packet`s and delivered to the addressed machine.
+    Packets,
+}
+
+/// A message in a packet network: three consecutive output values.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Packet {
+    pub dest: Word,
+    pub x: Word,
+    pub y: Word,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MachineState {
+    /// The machine can continue running.
+    Ready,
+    /// The machine needs input and its queue is empty.
+    Blocked,
+    Halted,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// A machine failed at runtime.
+    Machine { id: MachineId, error: crate::Error },
+    /// A machine sent a packet to an address with no machine or NAT.
+    BadAddress { id: MachineId, packet: Packet },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Machine { id, error } => write!(f, "Machine {} failed: {}", id, error),
+            Error::BadAddress { id, packet } => write!(
+                f,
+                "Machine {} sent a packet to unknown address {}",
+                id, packet.dest
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The reason `Network::run` returned.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// Every machine halted.
+    Halted,
+    /// No machine made progress in a whole round. The listed machines are
+    /// waiting for input that nobody will send.
+    Deadlock(Vec<MachineId>),
+    /// The network was idle and the NAT had nothing to send.
+    Idle,
+    /// The network was idle and the NAT sent this packet to machine 0.
+    NatWake(Packet),
+}
+
+/// What happened during one scheduling round.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Round {
+    /// At least one machine executed an instruction.
+    pub progress: bool,
+    /// Nothing was sent and every running machine polled an empty queue.
+    pub idle: bool,
+}
+
+/// The final state of a machine run by `threaded::run` or `future::run`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MachineReport {
+    pub state: MachineState,
+    /// Values output by a sink machine.
+    pub output: Vec<Word>,
+    /// The most recent value the machine output, whatever its route.
+    pub last_output: Option<Word>,
+}
+
+/// The result of running a network to completion on an executor.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Report {
+    /// Either `Outcome::Halted` or `Outcome::Deadlock`.
+    pub outcome: Outcome,
+    pub machines: Vec<MachineReport>,
+}
+
+impl Report {
+    pub(crate) fn new(machines: Vec<MachineReport>) -> Report {
+        let blocked: Vec<_> = (0..machines.len())
+            .filter(|&id| machines[id].state != MachineState::Halted)
+            .collect();
+        let outcome = if blocked.is_empty() {
+            Outcome::Halted
+        } else {
+            Outcome::Deadlock(blocked)
+        };
+        Report { outcome, machines }
+    }
+}
+
+/// A machine handed over to an executor.
+pub(crate) struct Node<M> {
+    pub program: Program<M>,
+    pub input: VecDeque<Word>,
+    pub targets: Vec<MachineId>,
+}
+
+#[derive(Debug)]
+struct Machine<M> {
+    program: Program<M>,
+    input: VecDeque<Word>,
+    route: Route,
+    output: Vec<Word>,
+    last_output: Option<Word>,
+    partial_packet: Vec<Word>,
+    state: MachineState,
+}
+
+#[derive(Debug)]
+struct Nat {
+    addr: Word,
+    packet: Option<Packet>,
+}
+
+/// Result of a single machine's turn.
+struct Turn {
+    progress: bool,
+    polled: bool,
+    output: Option<Word>,
+}
+
+#[derive(Debug)]
+pub struct Network<M = Dense> {
+    machines: Vec<Machine<M>>,
+    idle_input: Option<Word>,
+    nat: Option<Nat>,
+}
+
+impl<M: Memory> Default for Network<M> {
+    fn default() -> Network<M> {
+        Network {
+            machines: Vec::new(),
+            idle_input: None,
+            nat: None,
+        }
+    }
+}
+
+impl<M: Memory> Network<M> {
+    pub fn new() -> Network<M> {
+        Network::default()
+    }
+
+    /// Connects each program to the next one. The last program is a sink.
+    pub fn pipeline(programs: Vec<Program<M>>) -> Network<M> {
+        let mut network = Network::new();
+        let ids: Vec<_> = programs.into_iter().map(|p| network.add(p)).collect();
+        for pair in ids.windows(2) {
+            network.connect(pair[0], pair[1]);
+        }
+        network
+    }
+
+    /// Connects each program to the next one and the last to the first.
+    pub fn ring(programs: Vec<Program<M>>) -> Network<M> {
+        let mut network = Network::pipeline(programs);
+        if !network.is_empty() {
+            network.connect(network.len() - 1, 0);
+        }
+        network
+    }
+
+    /// Creates a packet network. Every machine receives its address as its
+    /// first input and reads -1 when no packet is waiting.
+    pub fn packet_switched(programs: Vec<Program<M>>) -> Network<M> {
+        let mut network = Network::new();
+        for program in programs {
+            let id = network.add(program);
+            network.set_route(id, Route::Packets);
+            network.push_input(id, id as Word);
+        }
+        network.set_idle_input(Some(-1));
+        network
+    }
+
+    /// Adds a machine whose output is kept in its output buffer.
+    pub fn add(&mut self, program: Program<M>) -> MachineId {
+        let state = if program.is_done() {
+            MachineState::Halted
+        } else {
+            MachineState::Ready
+        };
+        self.machines.push(Machine {
+            program,
+            input: VecDeque::new(),
+            route: Route::Sink,
+            output: Vec::new(),
+            last_output: None,
+            partial_packet: Vec::new(),
+            state,
+        });
+        self.machines.len() - 1
+    }
+
+    /// Sends the output of `from` to `to` as well as to any machines it is
+    /// already connected to.
+    pub fn connect(&mut self, from: MachineId, to: MachineId) {
+        assert!(to < self.machines.len(), "No machine {}", to);
+        let route = &mut self.machines[from].route;
+        match route {
+            Route::Direct(targets) => targets.push(to),
+            _ => *route = Route::Direct(vec![to]),
+        }
+    }
+
+    pub fn set_route(&mut self, id: MachineId, route: Route) {
+        if let Route::Direct(targets) = &route {
+            for &to in targets.iter() {
+                assert!(to < self.machines.len(), "No machine {}", to);
+            }
+        }
+        self.machines[id].route = route;
+    }
+
+    /// Sets the value machines read from an empty queue, or `None` to
+    /// block them until input arrives.
+    pub fn set_idle_input(&mut self, value: Option<Word>) {
+        self.idle_input = value;
+    }
+
+    /// Installs a NAT that receives packets sent to `addr`.
+    pub fn set_nat(&mut self, addr: Word) {
+        self.nat = Some(Nat { addr, packet: None });
+    }
+
+    /// The last packet the NAT received.
+    pub fn nat_packet(&self) -> Option<Packet> {
+        self.nat.as_ref().and_then(|nat| nat.packet)
+    }
+
+    pub fn len(&self) -> usize {
+        self.machines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.machines.is_empty()
+    }
+
+    pub fn program(&self, id: MachineId) -> &Program<M> {
+        &self.machines[id].program
+    }
+
+    pub fn state(&self, id: MachineId) -> MachineState {
+        self.machines[id].state
+    }
+
+    /// Machines that are waiting for input.
+    pub fn blocked(&self) -> Vec<MachineId> {
+        self.with_state(MachineState::Blocked)
+    }
+
+    /// Machines that have halted.
+    pub fn halted(&self) -> Vec<MachineId> {
+        self.with_state(MachineState::Halted)
+    }
+
+    fn with_state(&self, state: MachineState) -> Vec<MachineId> {
+        (0..self.machines.len())
+            .filter(|&id| self.machines[id].state == state)
+            .collect()
+    }
+
+    /// Queues an input value for a machine.
+    pub fn push_input(&mut self, id: MachineId, value: Word) {
+        let machine = &mut self.machines[id];
+        machine.input.push_back(value);
+        if machine.state == MachineState::Blocked {
+            machine.state = MachineState::Ready;
+        }
+    }
+
+    /// Returns the values a sink machine output since the last call.
+    pub fn take_output(&mut self, id: MachineId) -> Vec<Word> {
+        std::mem::take(&mut self.machines[id].output)
+    }
+
+    /// The most recent value a machine output, whatever its route.
+    pub fn last_output(&self, id: MachineId) -> Option<Word> {
+        self.machines[id].last_output
+    }
+
+    /// Splits the network into its machines for an executor that runs them
+    /// concurrently. Only `Sink` and `Direct` routes are supported, and
+    /// machines always block on empty input queues.
+    pub(crate) fn into_nodes(self) -> Vec<Node<M>> {
+        assert!(
+            self.idle_input.is_none() && self.nat.is_none(),
+            "Idle input and NATs need the round-robin scheduler"
+        );
+        self.machines
+            .into_iter()
+            .map(|machine| Node {
+                program: machine.program,
+                input: machine.input,
+                targets: match machine.route {
+                    Route::Sink => Vec::new(),
+                    Route::Direct(targets) => targets,
+                    Route::Packets => panic!("Packet routes need the round-robin scheduler"),
+                },
+            })
+            .collect()
+    }
+
+    /// Gives every machine that has not halted one turn.
+    pub fn run_round(&mut self) -> Result<Round, Error> {
+        let mut progress = false;
+        let mut sent = false;
+        let mut all_polled = true;
+        for id in 0..self.machines.len() {
+            if self.machines[id].state == MachineState::Halted {
+                continue;
+            }
+            let turn = self.turn(id)?;
+            progress |= turn.progress;
+            all_polled &= turn.polled;
+            if let Some(value) = turn.output {
+                sent = true;
+                self.route(id, value)?;
+            }
+        }
+        let queues_empty = self.machines.iter().all(|m| m.input.is_empty());
+        Ok(Round {
+            progress,
+            idle: self.idle_input.is_some() && !sent && all_polled && queues_empty,
+        })
+    }
+
+    /// Runs rounds until every machine halts, the network deadlocks or it
+    /// goes idle.
+    pub fn run(&mut self) -> Result<Outcome, Error> {
+        loop {
+            let round = self.run_round()?;
+            if self
+                .machines
+                .iter()
+                .all(|m| m.state == MachineState::Halted)
+            {
+                return Ok(Outcome::Halted);
+            }
+            if round.idle {
+                return Ok(match self.nat_packet() {
+                    Some(packet) if !self.machines.is_empty() => {
+                        self.push_input(0, packet.x);
+                        self.push_input(0, packet.y);
+                        Outcome::NatWake(packet)
+                    }
+                    _ => Outcome::Idle,
+                });
+            }
+            if !round.progress {
+                return Ok(Outcome::Deadlock(self.blocked()));
+            }
+        }
+    }
+
+    fn turn(&mut self, id: MachineId) -> Result<Turn, Error> {
+        let idle_input = self.idle_input;
+        let machine = &mut self.machines[id];
+        let pc = machine.program.pc();
+        let mut consumed = false;
+        let mut polled = false;
+        let queue = &mut machine.input;
+        let mut input = || match queue.pop_front() {
+            Some(value) => {
+                consumed = true;
+                Some(value)
+            }
+            None if !polled => {
+                polled = true;
+                idle_input
+            }
+            None => None,
+        };
+        let state = machine
+            .program
+            .run_partial(&mut input)
+            .map_err(|error| Error::Machine { id, error })?;
+
+        let mut output = None;
+        machine.state = match state {
+            RunState::Output(value) => {
+                output = Some(value);
+                MachineState::Ready
+            }
+            RunState::NeedsInput => MachineState::Blocked,
+            RunState::Halted => MachineState::Halted,
+        };
+        Ok(Turn {
+            progress: consumed || state != RunState::NeedsInput || machine.program.pc() != pc,
+            polled: polled && idle_input.is_some(),
+            output,
+        })
+    }
+
+    fn route(&mut self, from: MachineId, value: Word) -> Result<(), Error> {
+        let machine = &mut self.machines[from];
+        machine.last_output = Some(value);
+        match &machine.route {
+            Route::Sink => machine.output.push(value),
+            Route::Direct(targets) => {
+                for to in targets.clone() {
+                    self.push_input(to, value);
+                }
+            }
+            Route::Packets => {
+                machine.partial_packet.push(value);
+                if machine.partial_packet.len() == 3 {
+                    let packet = Packet {
+                        dest: machine.partial_packet[0],
+                        x: machine.partial_packet[1],
+                        y: machine.partial_packet[2],
+                    };
+                    machine.partial_packet.clear();
+                    self.deliver(from, packet)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn deliver(&mut self, from: MachineId, packet: Packet) -> Result<(), Error> {
+        if let Some(nat) = self.nat.as_mut().filter(|nat| nat.addr == packet.dest) {
+            nat.packet = Some(packet);
+            return Ok(());
+        }
+        match usize::try_from(packet.dest) {
+            Ok(to) if to < self.machines.len() => {
+                self.push_input(to, packet.x);
+                self.push_input(to, packet.y);
+                Ok(())
+            }
+            _ => Err(Error::BadAddress { id: from, packet }),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::asm::assemble;
+
+    // Reads a phase setting and a signal, and outputs the amplified signal.
+    const AMPLIFIER: [Word; 17] = [
+        3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+    ];
+
+    // Amplifier that loops on its input until a counter runs out.
+    const FEEDBACK: [Word; 29] = [
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    /// A ring of feedback amplifiers with the given phases, and the first
+    /// signal queued. The executors test against it too.
+    pub(crate) fn feedback_loop(phases: &[Word]) -> Network {
+        let programs = (0..phases.len()).map(|_| Program::new(&FEEDBACK)).collect();
+        let mut network = Network::ring(programs);
+        for (id, &phase) in phases.iter().enumerate() {
+            network.push_input(id, phase);
+        }
+        network.push_input(0, 0);
+        network
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let programs = (0..5).map(|_| Program::new(&AMPLIFIER)).collect();
+        let mut network: Network = Network::pipeline(programs);
+        for (id, &phase) in [4, 3, 2, 1, 0].iter().enumerate() {
+            network.push_input(id, phase);
+        }
+        network.push_input(0, 0);
+        assert_eq!(network.run().unwrap(), Outcome::Halted);
+        assert_eq!(network.take_output(4), vec![43210]);
+        assert!(network.take_output(4).is_empty());
+    }
+
+    #[test]
+    fn test_ring() {
+        let mut network = feedback_loop(&[9, 8, 7, 6, 5]);
+        assert_eq!(network.run().unwrap(), Outcome::Halted);
+        assert_eq!(network.last_output(4), Some(139629729));
+        assert_eq!(network.halted(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_fan_out() {
+        let mut network = Network::new();
+        let source = network.add(Program::new(&[104, 1, 104, 2, 99]));
+        let left = network.add(Program::new(&[3, 0, 4, 0, 3, 0, 4, 0, 99]));
+        let right = network.add(Program::new(&[3, 0, 3, 1, 1, 0, 1, 0, 4, 0, 99]));
+        network.connect(source, left);
+        network.connect(source, right);
+        assert_eq!(network.run().unwrap(), Outcome::Halted);
+        assert_eq!(network.take_output(left), vec![1, 2]);
+        assert_eq!(network.take_output(right), vec![3]);
+    }
+
+    #[test]
+    fn test_deadlock() {
+        let echo = [3, 0, 4, 0, 99];
+        let mut network: Network = Network::ring(vec![Program::new(&echo), Program::new(&echo)]);
+        assert_eq!(network.run().unwrap(), Outcome::Deadlock(vec![0, 1]));
+        assert_eq!(network.state(0), MachineState::Blocked);
+
+        network.push_input(1, 5);
+        assert_eq!(network.state(1), MachineState::Ready);
+        assert_eq!(network.run().unwrap(), Outcome::Halted);
+        assert_eq!(network.last_output(0), Some(5));
+
+        let mut network = Network::pipeline(vec![Program::new(&[104, 1, 99]), Program::new(&echo)]);
+        network.add(Program::new(&echo));
+        assert_eq!(network.run().unwrap(), Outcome::Deadlock(vec![2]));
+        assert_eq!(network.halted(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_machine_error() {
+        let mut network: Network =
+            Network::pipeline(vec![Program::new(&[104, 1, 99]), Program::new(&[3, 0, 77])]);
+        match network.run() {
+            Err(Error::Machine { id: 1, .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_packets() {
+        // Machine 0 sends (7, 8) to machine 1 and then discards its input.
+        // Every other machine forwards the packets it receives to the NAT.
+        let code = assemble(
+            "
+                    in [addr]
+                    jt [addr], #recv
+                    out #1
+                    out #7
+                    out #8
+            idle:   in [x]
+                    jt #1, #idle
+            recv:   in [x]
+                    eq [x], #-1, [t]
+                    jt [t], #recv
+                    in [y]
+                    out #255
+                    out [x]
+                    out [y]
+                    jt #1, #recv
+            addr:   .data 0
+            x:      .data 0
+            y:      .data 0
+            t:      .data 0
+            ",
+        )
+        .unwrap();
+        // Polls for packets forever.
+        let poll = [3, 5, 1105, 1, 0, 0];
+        let mut network: Network = Network::packet_switched(vec![Program::new(&poll)]);
+        network.set_nat(255);
+        assert_eq!(network.run().unwrap(), Outcome::Idle);
+
+        let mut network: Network =
+            Network::packet_switched((0..3).map(|_| Program::new(&code)).collect());
+        network.set_nat(255);
+        let packet = Packet {
+            dest: 255,
+            x: 7,
+            y: 8,
+        };
+        assert_eq!(network.run().unwrap(), Outcome::NatWake(packet));
+        assert_eq!(network.run().unwrap(), Outcome::NatWake(packet));
+        assert_eq!(network.nat_packet(), Some(packet));
+
+        let programs = (0..3).map(|_| Program::new(&code)).collect();
+        let mut network: Network = Network::packet_switched(programs);
+        match network.run() {
+            Err(Error::BadAddress { id: 1, packet }) => assert_eq!(packet.dest, 255),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,407 @@
+//! Saved machine state and its on-disk format.
+//!
+//! A serialized snapshot is a little-endian binary blob:
+//!
+//! ```text
+//! magic     4 bytes   "ICSN"
+//! version   u16       currently 2
+//! flags     u16       bit 0 set if the program has halted,
+//!                     bit 1 set if cells are 128 bits wide
+//! pc        u64
+//! rel_base  i64 or i128
+//! length    u64       one past the highest address in use
+//! runs      u64       number of runs of memory cells
+//! ```
+//!
+//! followed by each run, in increasing address order:
+//!
+//! ```text
+//! addr      u64       address of the first cell
+//! count     u64       number of cells
+//! cells     count * (i64 or i128)
+//! ```
+//!
+//! Cells outside every run are zero, so a program that wrote to a few far
+//! apart addresses of `Paged` memory stays small. Version 1 snapshots,
+//! which store `length` cells after the length instead of runs, can still
+//! be read.
+//!
+//! Snapshots are written with the cell width of the build (see `Word`).
+//! Either width can be read as long as every value fits in a `Word`.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::memory::LimitExceeded;
+use crate::Word;
+
+const MAGIC: &[u8; 4] = b"ICSN";
+const VERSION: u16 = 2;
+/// Stores every cell below the length, without runs.
+const VERSION_DENSE: u16 = 1;
+const FLAG_DONE: u16 = 1;
+const FLAG_WIDE: u16 = 2;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    BadMagic([u8; 4]),
+    UnsupportedVersion(u16),
+    /// The pc does not point into memory, the memory does not fit in
+    /// this platform's address space, the runs overlap or lie past the
+    /// length, or a value does not fit in a `Word`.
+    Corrupt,
+    /// The memory does not fit in the backend it is restored into.
+    MemoryLimit(LimitExceeded),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Error::*;
+
+        match self {
+            Io(e) => write!(f, "I/O error: {}", e),
+            BadMagic(m) => write!(f, "Not a snapshot: bad magic {:?}", m),
+            UnsupportedVersion(v) => write!(f, "Unsupported snapshot version: {}", v),
+            Corrupt => write!(f, "Corrupt snapshot"),
+            MemoryLimit(e) => write!(
+                f,
+                "Snapshot memory does not fit: address {} exceeds memory limit {}",
+                e.addr, e.limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+/// The complete state of a `Program`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Snapshot {
+    pub pc: usize,
+    pub rel_base: Word,
+    pub done: bool,
+    /// One past the highest address the program touched.
+    pub len: usize,
+    /// Runs of consecutive cells with the address of their first cell, in
+    /// increasing address order. Cells outside every run are zero.
+    pub memory: Vec<(usize, Vec<Word>)>,
+}
+
+fn read_u16(reader: &mut dyn Read) -> io::Result<u16> {
+    let mut buf = [0; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut dyn Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_word(reader: &mut dyn Read, wide: bool) -> Result<Word, Error> {
+    if wide {
+        let mut buf = [0; 16];
+        reader.read_exact(&mut buf)?;
+        Word::try_from(i128::from_le_bytes(buf)).map_err(|_| Error::Corrupt)
+    } else {
+        Ok(Word::from(read_u64(reader)? as i64))
+    }
+}
+
+fn read_cells(reader: &mut dyn Read, count: u64, wide: bool) -> Result<Vec<Word>, Error> {
+    // Grow as the cells arrive, so a corrupt count fails at the end of the
+    // input instead of allocating it up front.
+    let mut cells = Vec::new();
+    for _ in 0..count {
+        cells.push(read_word(reader, wide)?);
+    }
+    Ok(cells)
+}
+
+impl Snapshot {
+    pub fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        let mut flags = if self.done { FLAG_DONE } else { 0 };
+        if std::mem::size_of::<Word>() == 16 {
+            flags |= FLAG_WIDE;
+        }
+        writer.write_all(&flags.to_le_bytes())?;
+        writer.write_all(&(self.pc as u64).to_le_bytes())?;
+        writer.write_all(&self.rel_base.to_le_bytes())?;
+        writer.write_all(&(self.len as u64).to_le_bytes())?;
+        writer.write_all(&(self.memory.len() as u64).to_le_bytes())?;
+        for (addr, cells) in self.memory.iter() {
+            writer.write_all(&(*addr as u64).to_le_bytes())?;
+            writer.write_all(&(cells.len() as u64).to_le_bytes())?;
+            for value in cells.iter() {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut dyn Read) -> Result<Snapshot, Error> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::BadMagic(magic));
+        }
+        let version = read_u16(reader)?;
+        if version != VERSION && version != VERSION_DENSE {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let flags = read_u16(reader)?;
+        let pc = read_u64(reader)?;
+        let wide = flags & FLAG_WIDE != 0;
+        let rel_base = read_word(reader, wide)?;
+        let len = read_u64(reader)?;
+        if len > usize::MAX as u64 {
+            return Err(Error::Corrupt);
+        }
+
+        let mut memory = Vec::new();
+        if version == VERSION_DENSE {
+            if len > usize::MAX as u64 / 8 {
+                return Err(Error::Corrupt);
+            }
+            memory.push((0, read_cells(reader, len, wide)?));
+        } else {
+            let runs = read_u64(reader)?;
+            for _ in 0..runs {
+                let addr = read_u64(reader)?;
+                let count = read_u64(reader)?;
+                // Checked before reading, so the cells cannot outgrow the
+                // memory they claim to be in.
+                if count > len || addr > len - count {
+                    return Err(Error::Corrupt);
+                }
+                memory.push((addr as usize, read_cells(reader, count, wide)?));
+            }
+        }
+        let snapshot = Snapshot {
+            pc: pc as usize,
+            rel_base,
+            done: flags & FLAG_DONE != 0,
+            len: len as usize,
+            memory,
+        };
+        snapshot.check()?;
+        Ok(snapshot)
+    }
+
+    /// Fails with `Error::Corrupt` unless the pc points into memory and the
+    /// runs are in order, apart, and below the length.
+    pub fn check(&self) -> Result<(), Error> {
+        if self.pc >= self.len {
+            return Err(Error::Corrupt);
+        }
+        let mut end = 0;
+        for (addr, cells) in self.memory.iter() {
+            if *addr < end || cells.len() > self.len || *addr > self.len - cells.len() {
+                return Err(Error::Corrupt);
+            }
+            end = addr + cells.len();
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let word = std::mem::size_of::<Word>();
+        let cells: usize = self.memory.iter().map(|(_, cells)| cells.len()).sum();
+        let mut bytes = Vec::with_capacity(32 + self.memory.len() * 16 + (cells + 1) * word);
+        self.write_to(&mut bytes)
+            .expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Snapshot, Error> {
+        Snapshot::read_from(&mut bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::debug::test::COUNTDOWN;
+    use crate::memory::{Bounded, Dense, Paged, PAGE_SIZE};
+    use crate::{Program, RunState};
+
+    fn outputs(prog: &mut Program) -> Vec<Word> {
+        let mut output = Vec::new();
+        while let RunState::Output(value) = prog.run_partial(&mut || None).unwrap() {
+            output.push(value);
+        }
+        output
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut prog = Program::new(&COUNTDOWN);
+        prog.run_partial(&mut || Some(4)).unwrap();
+        let snapshot = prog.snapshot();
+        assert_eq!(snapshot.pc, 4);
+        assert!(!snapshot.done);
+
+        assert_eq!(outputs(&mut prog), vec![3, 2, 1]);
+        assert!(prog.is_done());
+        assert_ne!(prog.snapshot(), snapshot);
+
+        prog.restore(&snapshot).unwrap();
+        assert_eq!(prog.snapshot(), snapshot);
+        assert_eq!(outputs(&mut prog), vec![3, 2, 1]);
+
+        let mut branch = Program::from_snapshot(&snapshot, Dense::new()).unwrap();
+        assert_eq!(outputs(&mut branch), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_restore_errors() {
+        let mut prog = Program::new(&COUNTDOWN);
+        let good = prog.snapshot();
+
+        let mut bad = good.clone();
+        bad.pc = COUNTDOWN.len();
+        assert!(matches!(prog.restore(&bad), Err(Error::Corrupt)));
+        assert!(matches!(
+            Program::from_snapshot(&bad, Dense::new()),
+            Err(Error::Corrupt)
+        ));
+        let mut bad = good.clone();
+        bad.memory.push((COUNTDOWN.len(), vec![1]));
+        assert!(matches!(prog.restore(&bad), Err(Error::Corrupt)));
+
+        // A rejected snapshot leaves the program as it was.
+        assert_eq!(prog.snapshot(), good);
+
+        // Memory that is only long, not large, fits in any backend but a
+        // bounded one.
+        let long = Snapshot {
+            pc: 0,
+            rel_base: 0,
+            done: false,
+            len: 1 << 60,
+            memory: vec![(0, vec![99])],
+        };
+        let branch = Program::from_snapshot(&long, Dense::new()).unwrap();
+        assert_eq!(branch.snapshot(), long);
+        assert!(matches!(
+            Program::from_snapshot(&long, Bounded::new(1 << 20)),
+            Err(Error::MemoryLimit(_))
+        ));
+        let mut large = long.clone();
+        large.memory = vec![((1 << 60) - 1, vec![1])];
+        assert!(matches!(
+            Program::from_snapshot(&large, Dense::new()),
+            Err(Error::MemoryLimit(_))
+        ));
+        assert!(Program::from_snapshot(&large, Paged::new()).is_ok());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut prog = Program::new(&[109, -7, 3, 0, 99]);
+        prog.run_partial(&mut || Some(Word::MIN)).unwrap();
+        let snapshot = prog.snapshot();
+        assert!(snapshot.done);
+        let bytes = snapshot.to_bytes();
+        assert_eq!(&bytes[..6], b"ICSN\x02\x00");
+        assert_eq!(bytes.len(), 48 + 6 * std::mem::size_of::<Word>());
+        assert_eq!(Snapshot::from_bytes(&bytes).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        let bytes = Program::new(&[99]).snapshot().to_bytes();
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert!(matches!(
+            Snapshot::from_bytes(&bad),
+            Err(Error::BadMagic(_))
+        ));
+
+        let mut bad = bytes.clone();
+        bad[4] = 3;
+        assert!(matches!(
+            Snapshot::from_bytes(&bad),
+            Err(Error::UnsupportedVersion(3))
+        ));
+
+        let mut bad = bytes.clone();
+        bad[8] = 1;
+        assert!(matches!(Snapshot::from_bytes(&bad), Err(Error::Corrupt)));
+
+        assert!(matches!(
+            Snapshot::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::Io(_))
+        ));
+
+        // Runs past the length or overlapping the previous run.
+        let mut snapshot = Program::new(&[99, 0]).snapshot();
+        snapshot.memory = vec![(1, vec![7, 7])];
+        assert!(matches!(
+            Snapshot::from_bytes(&snapshot.to_bytes()),
+            Err(Error::Corrupt)
+        ));
+        snapshot.memory = vec![(0, vec![99, 7]), (1, vec![7])];
+        assert!(matches!(
+            Snapshot::from_bytes(&snapshot.to_bytes()),
+            Err(Error::Corrupt)
+        ));
+    }
+
+    #[test]
+    fn test_paged_snapshot() {
+        let code = [1101, 1, 1, 1 << 40, 4, 1 << 40, 99];
+        let mut prog = Program::with_memory(&code, Paged::new());
+        prog.run_partial(&mut || None).unwrap();
+        let snapshot = prog.snapshot();
+        assert_eq!(snapshot.len, (1 << 40) + 1);
+        assert_eq!(snapshot.memory.len(), 2);
+        assert_eq!(snapshot.memory[1], (1 << 40, vec![2]));
+
+        let bytes = snapshot.to_bytes();
+        assert!(bytes.len() < 2 * PAGE_SIZE * std::mem::size_of::<Word>());
+        let snapshot = Snapshot::from_bytes(&bytes).unwrap();
+        let mut copy = Program::from_snapshot(&snapshot, Paged::new()).unwrap();
+        assert_eq!(copy.peek(1 << 40), 2);
+        assert_eq!(copy.memory().num_pages(), 2);
+        assert_eq!(copy.run_partial(&mut || None).unwrap(), RunState::Halted);
+    }
+
+    fn wide_snapshot(rel_base: i128, memory: &[i128]) -> Vec<u8> {
+        let mut bytes = b"ICSN\x01\x00\x02\x00".to_vec();
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&rel_base.to_le_bytes());
+        bytes.extend_from_slice(&(memory.len() as u64).to_le_bytes());
+        for value in memory.iter() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_wide_cells() {
+        let snapshot = Snapshot::from_bytes(&wide_snapshot(-5, &[104, 7, 99])).unwrap();
+        assert_eq!(snapshot.rel_base, -5);
+        assert_eq!(snapshot.len, 3);
+        assert_eq!(snapshot.memory, vec![(0, vec![104, 7, 99])]);
+
+        let huge = wide_snapshot(0, &[104, 1 << 100, 99]);
+        if cfg!(feature = "i128") {
+            assert!(Snapshot::from_bytes(&huge).is_ok());
+        } else {
+            assert!(matches!(Snapshot::from_bytes(&huge), Err(Error::Corrupt)));
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Runs every machine of a `Network` on its own thread.
+//!
+//! Machines exchange values over `std::sync::mpsc` channels. A shared
+//! counter of values in flight lets the executor notice when every machine
+//! that has not halted is waiting for input nobody will send, in which case
+//! the waiting machines are stopped and reported as blocked.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+
+use crate::memory::Memory;
+use crate::network::{Error, MachineId, MachineReport, MachineState, Network, Report};
+use crate::{Program, RunState, Word};
+
+#[derive(Debug)]
+struct State {
+    /// Values sent to each machine that it has not received yet.
+    queued: Vec<usize>,
+    waiting: Vec<bool>,
+    finished: Vec<bool>,
+    /// Set on deadlock or when a machine fails.
+    stop: bool,
+}
+
+impl State {
+    fn deadlocked(&self) -> bool {
+        let live: Vec<_> = (0..self.finished.len())
+            .filter(|&id| !self.finished[id])
+            .collect();
+        !live.is_empty()
+            && live
+                .iter()
+                .all(|&id| self.waiting[id] && self.queued[id] == 0)
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    fn send(&self, to: MachineId, sender: &Sender<Word>, value: Word) {
+        // The receiver is gone once its machine has finished, and the value
+        // can be dropped.
+        if sender.send(value).is_ok() {
+            self.lock().queued[to] += 1;
+            self.wake.notify_all();
+        }
+    }
+
+    /// Waits for the next input value. Returns `None` if the network was
+    /// stopped while waiting.
+    fn receive(&self, id: MachineId, receiver: &Receiver<Word>) -> Option<Word> {
+        let mut state = self.lock();
+        while state.queued[id] == 0 {
+            if state.stop {
+                return None;
+            }
+            state.waiting[id] = true;
+            if state.deadlocked() {
+                state.stop = true;
+                self.wake.notify_all();
+                return None;
+            }
+            state = self.wake.wait(state).unwrap();
+            state.waiting[id] = false;
+        }
+        state.queued[id] -= 1;
+        drop(state);
+        receiver.recv().ok()
+    }
+
+    fn finish(&self, id: MachineId, failed: bool) {
+        let mut state = self.lock();
+        state.finished[id] = true;
+        if failed || state.deadlocked() {
+            state.stop = true;
+        }
+        self.wake.notify_all();
+    }
+}
+
+fn run_machine<M: Memory>(
+    id: MachineId,
+    program: &mut Program<M>,
+    receiver: &Receiver<Word>,
+    targets: &[(MachineId, Sender<Word>)],
+    shared: &Shared,
+) -> Result<MachineReport, crate::Error> {
+    let mut report = MachineReport {
+        state: MachineState::Ready,
+        output: Vec::new(),
+        last_output: None,
+    };
+    loop {
+        match program.run_partial(&mut || shared.receive(id, receiver))? {
+            RunState::Output(value) => {
+                report.last_output = Some(value);
+                if targets.is_empty() {
+                    report.output.push(value);
+                }
+                for (to, sender) in targets.iter() {
+                    shared.send(*to, sender, value);
+                }
+            }
+            RunState::NeedsInput => {
+                report.state = MachineState::Blocked;
+                return Ok(report);
+            }
+            RunState::Halted => {
+                report.state = MachineState::Halted;
+                return Ok(report);
+            }
+        }
+    }
+}
+
+/// Runs the machines of `network` concurrently until every one of them
+/// has halted or is blocked on input. Fails with the error of the first
+/// machine that failed.
+///
+/// Panics if the network uses packet routes, idle input or a NAT, which
+/// only make sense with the round-robin scheduler of `Network::run`.
+pub fn run<M: Memory + Send + 'static>(network: Network<M>) -> Result<Report, Error> {
+    let nodes = network.into_nodes();
+    let len = nodes.len();
+    let (senders, receivers): (Vec<Sender<Word>>, Vec<_>) =
+        (0..len).map(|_| mpsc::channel()).unzip();
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queued: nodes.iter().map(|node| node.input.len()).collect(),
+            waiting: vec![false; len],
+            finished: vec![false; len],
+            stop: false,
+        }),
+        wake: Condvar::new(),
+    });
+
+    // All initial input has to be queued before any machine starts, or a
+    // machine could see output from another one before its own input.
+    for (node, sender) in nodes.iter().zip(senders.iter()) {
+        for &value in node.input.iter() {
+            sender.send(value).unwrap();
+        }
+    }
+
+    let mut handles = Vec::with_capacity(len);
+    for (id, (node, receiver)) in nodes.into_iter().zip(receivers).enumerate() {
+        let targets: Vec<_> = node
+            .targets
+            .iter()
+            .map(|&to| (to, senders[to].clone()))
+            .collect();
+        let shared = shared.clone();
+        let mut program = node.program;
+        handles.push(thread::spawn(move || {
+            let result = run_machine(id, &mut program, &receiver, &targets, &shared);
+            shared.finish(id, result.is_err());
+            result
+        }));
+    }
+    drop(senders);
+
+    let mut machines = Vec::with_capacity(len);
+    let mut first_error = None;
+    for (id, handle) in handles.into_iter().enumerate() {
+        match handle.join().expect("Machine thread panicked") {
+            Ok(report) => machines.push(report),
+            Err(error) => {
+                if first_error.is_none() {
+                    first_error = Some(Error::Machine { id, error });
+                }
+            }
+        }
+    }
+    match first_error {
+        Some(error) => Err(error),
+        None => Ok(Report::new(machines)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::network::test::feedback_loop;
+    use crate::network::Outcome;
+
+    #[test]
+    fn test_feedback_loop() {
+        let mut network = feedback_loop(&[9, 8, 7, 6, 5]);
+        assert_eq!(network.run().unwrap(), Outcome::Halted);
+        let expected = network.last_output(4);
+        assert_eq!(expected, Some(139629729));
+
+        let report = run(feedback_loop(&[9, 8, 7, 6, 5])).unwrap();
+        assert_eq!(report.outcome, Outcome::Halted);
+        assert_eq!(report.machines[4].last_output, expected);
+    }
+
+    #[test]
+    fn test_sinks() {
+        let mut network = Network::new();
+        let source = network.add(Program::new(&[104, 1, 104, 2, 99]));
+        let left = network.add(Program::new(&[3, 0, 4, 0, 3, 0, 4, 0, 99]));
+        let right = network.add(Program::new(&[3, 0, 3, 1, 1, 0, 1, 0, 4, 0, 99]));
+        network.connect(source, left);
+        network.connect(source, right);
+        let report = run(network).unwrap();
+        assert_eq!(report.outcome, Outcome::Halted);
+        assert!(report.machines[source].output.is_empty());
+        assert_eq!(report.machines[left].output, vec![1, 2]);
+        assert_eq!(report.machines[right].output, vec![3]);
+    }
+
+    #[test]
+    fn test_deadlock() {
+        let echo = [3, 0, 4, 0, 99];
+        let network = Network::ring(vec![Program::new(&echo), Program::new(&echo)]);
+        let report = run(network).unwrap();
+        assert_eq!(report.outcome, Outcome::Deadlock(vec![0, 1]));
+        assert_eq!(report.machines[0].state, MachineState::Blocked);
+
+        let mut network = Network::pipeline(vec![Program::new(&[104, 1, 99]), Program::new(&echo)]);
+        network.add(Program::new(&echo));
+        let report = run(network).unwrap();
+        assert_eq!(report.outcome, Outcome::Deadlock(vec![2]));
+        assert_eq!(report.machines[1].output, vec![1]);
+    }
+
+    #[test]
+    fn test_error() {
+        let mut network = Network::ring(vec![
+            Program::new(&[3, 0, 4, 0, 99]),
+            Program::new(&[104, 1, 77]),
+        ]);
+        network.push_input(0, 5);
+        match run(network) {
+            Err(Error::Machine { id: 1, .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Per-instruction execution events and observers that record them.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+use crate::{Instruction, Word};
+
+/// A memory cell changed by an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MemWrite {
+    pub addr: usize,
+    pub old: Word,
+    pub new: Word,
+}
+
+/// An executed instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Event {
+    pub pc: usize,
+    /// The raw instruction word at `pc`.
+    pub word: Word,
+    pub instruction: Instruction,
+    /// Memory written by the instruction, if any.
+    pub write: Option<MemWrite>,
+    /// The old and new relative base if the instruction changed it.
+    pub rel_base: Option<(Word, Word)>,
+    operands: [Word; 2],
+    num_operands: usize,
+}
+
+impl Event {
+    pub(crate) fn new(pc: usize, word: Word, instruction: Instruction) -> Event {
+        Event {
+            pc,
+            word,
+            instruction,
+            write: None,
+            rel_base: None,
+            operands: [0; 2],
+            num_operands: 0,
+        }
+    }
+
+    pub(crate) fn push_operand(&mut self, value: Word) {
+        self.operands[self.num_operands] = value;
+        self.num_operands += 1;
+    }
+
+    /// Values of the parameters the instruction reads, after resolving
+    /// their modes. Destination parameters are reported through `write`.
+    pub fn operands(&self) -> &[Word] {
+        &self.operands[..self.num_operands]
+    }
+}
+
+pub trait Observer {
+    fn on_instruction(&mut self, event: &Event);
+}
+
+/// Observer used when nothing is watching the program.
+pub(crate) struct Quiet;
+
+impl Observer for Quiet {
+    #[inline(always)]
+    fn on_instruction(&mut self, _event: &Event) {}
+}
+
+impl<F: FnMut(&Event)> Observer for F {
+    fn on_instruction(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+/// Writes every event as a line of JSON.
+///
+/// Write errors stop the trace; the first one is returned by `finish`.
+pub struct JsonTrace<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonTrace<W> {
+    pub fn new(writer: W) -> JsonTrace<W> {
+        JsonTrace {
+            writer,
+            error: None,
+        }
+    }
+
+    /// Flushes the trace and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Formats an event as a single-line JSON object.
+pub fn to_json(event: &Event) -> String {
+    let operands: Vec<_> = event.operands().iter().map(Word::to_string).collect();
+    let mut json = format!(
+        "{{\"pc\":{},\"word\":{},\"op\":\"{}\",\"operands\":[{}]",
+        event.pc,
+        event.word,
+        event.instruction.op.mnemonic(),
+        operands.join(",")
+    );
+    if let Some(write) = event.write {
+        json.push_str(&format!(
+            ",\"write\":{{\"addr\":{},\"old\":{},\"new\":{}}}",
+            write.addr, write.old, write.new
+        ));
+    }
+    if let Some((old, new)) = event.rel_base {
+        json.push_str(&format!(
+            ",\"rel_base\":{{\"old\":{},\"new\":{}}}",
+            old, new
+        ));
+    }
+    json.push('}');
+    json
+}
+
+impl<W: Write> Observer for JsonTrace<W> {
+    fn on_instruction(&mut self, event: &Event) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.writer, "{}", to_json(event)) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+/// Keeps the last `capacity` events.
+#[derive(Debug)]
+pub struct Recorder {
+    capacity: usize,
+    events: VecDeque<Event>,
+}
+
+impl Recorder {
+    pub fn new(capacity: usize) -> Recorder {
+        Recorder {
+            capacity,
+            events: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Recorded events, oldest first.
+    pub fn events(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Writes the recorded events as JSON lines, oldest first.
+    pub fn dump(&self, writer: &mut dyn Write) -> io::Result<()> {
+        for event in self.events.iter() {
+            writeln!(writer, "{}", to_json(event))?;
+        }
+        Ok(())
+    }
+}
+
+impl Observer for Recorder {
+    fn on_instruction(&mut self, event: &Event) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(*event);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Error, Operator, Program, RunState};
+
+    #[test]
+    fn test_events() {
+        let code = [109, 5, 21101, 2, 3, 0, 4, 5, 99];
+        let mut events = Vec::new();
+        let mut prog = Program::new(&code);
+        let state = prog
+            .run_partial_observed(&mut || None, &mut |e: &Event| events.push(*e))
+            .unwrap();
+        assert_eq!(state, RunState::Output(5));
+        assert_eq!(events.len(), 3);
+
+        assert_eq!(events[0].instruction.op, Operator::AdjRelBase);
+        assert_eq!(events[0].operands(), &[5]);
+        assert_eq!(events[0].rel_base, Some((0, 5)));
+
+        assert_eq!(events[1].pc, 2);
+        assert_eq!(events[1].word, 21101);
+        assert_eq!(events[1].operands(), &[2, 3]);
+        assert_eq!(
+            events[1].write,
+            Some(MemWrite {
+                addr: 5,
+                old: 0,
+                new: 5
+            })
+        );
+
+        assert_eq!(events[2].operands(), &[5]);
+        assert_eq!(events[2].write, None);
+    }
+
+    #[test]
+    fn test_json_trace() {
+        let code = [3, 0, 4, 0, 99];
+        let mut trace = JsonTrace::new(Vec::new());
+        let mut input = Some(7);
+        let mut prog = Program::new(&code);
+        prog.run_partial_observed(&mut || input.take(), &mut trace)
+            .unwrap();
+        prog.run_partial_observed(&mut || None, &mut trace).unwrap();
+        let text = String::from_utf8(trace.finish().unwrap()).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"pc":0,"word":3,"op":"in","operands":[],"write":{"addr":0,"old":3,"new":7}}"#,
+                r#"{"pc":2,"word":4,"op":"out","operands":[7]}"#,
+                r#"{"pc":4,"word":99,"op":"hlt","operands":[]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recorder() {
+        // Counts down from 3 and then jumps to an invalid opcode.
+        let code = [1101, 3, 0, 13, 1001, 13, -1, 13, 1005, 13, 4, 0, 99, 0];
+        let mut recorder = Recorder::new(4);
+        let mut prog = Program::new(&code);
+        match prog.run_partial_observed(&mut || None, &mut recorder) {
+            Err(Error::UnknownOpcode(0)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(recorder.len(), 4);
+        let pcs: Vec<_> = recorder.events().map(|e| e.pc).collect();
+        assert_eq!(pcs, vec![4, 8, 4, 8]);
+        let last = recorder.events().last().unwrap();
+        assert_eq!(last.pc, 8);
+        assert_eq!(last.operands(), &[0, 4]);
+
+        let mut dump = Vec::new();
+        recorder.dump(&mut dump).unwrap();
+        assert_eq!(String::from_utf8(dump).unwrap().lines().count(), 4);
+    }
+}
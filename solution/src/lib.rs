@@ -0,0 +1,27 @@
+//! The interface every day's puzzle solution implements, so a single
+//! runner can solve and time all of them.
+
+use std::error::Error;
+use std::fmt;
+
+/// A puzzle answer. Most are numbers; some are text.
+pub type Answer = Box<dyn fmt::Display>;
+
+pub type Result = std::result::Result<Answer, Box<dyn Error>>;
+
+pub trait Solution {
+    /// Day of the puzzle, from 1 to 25.
+    fn day(&self) -> u32;
+
+    /// Solves the first part of the puzzle for `input`, the contents of
+    /// the puzzle input file.
+    fn part1(&self, input: &str) -> Result;
+
+    /// Solves the second part of the puzzle for `input`.
+    fn part2(&self, input: &str) -> Result;
+}
+
+/// Wraps a value as an `Answer`.
+pub fn answer<T: fmt::Display + 'static>(value: T) -> Result {
+    Ok(Box::new(value))
+}